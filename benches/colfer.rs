@@ -0,0 +1,206 @@
+extern crate colfer_rs;
+#[macro_use]
+extern crate criterion;
+
+#[cfg(feature = "arena")]
+use colfer_rs::{Bump, ColferTypesArena};
+use colfer_rs::{ColferSerializable, ColferTypes};
+use criterion::{Criterion, Throughput};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts heap allocations, to report allocations per operation next to the timings.
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn report_allocs<F: FnMut()>(name: &str, mut f: F) {
+    const ROUNDS: usize = 1000;
+    let before = ALLOCS.load(Ordering::Relaxed);
+    for _ in 0..ROUNDS {
+        f();
+    }
+    let n = ALLOCS.load(Ordering::Relaxed) - before;
+    println!("{}: {:.2} allocs/op", name, n as f64 / ROUNDS as f64);
+}
+
+// The records of upstream Colfer's Go benchmarks. Their schema has key int64, host text,
+// port uint16, size int64, hash uint64, ratio float64 and route bool, which map onto I64,
+// S, U16, U32, U64, F64 and B here.
+fn small() -> Vec<ColferTypes<'static>> {
+    let records: [(i64, &'static str, u16, u32, u64, f64, bool); 4] = [
+        (1234567890, "db003lz12", 389, 452, 0x488b5c2428488918, 0.99, true),
+        (1234567891, "localhost", 22, 4096, 0x243048899c24c824, 0.20, false),
+        (1234567892, "kdc.local", 88, 1984, 0x000048891c24485c, 0.06, false),
+        (1234567893, "vhost8.dmz.example.com", 27017, 59741, 0x5c2408488b9c2489, 0.0, true),
+    ];
+    records
+        .iter()
+        .map(|&(key, host, port, size, hash, ratio, route)| ColferTypes {
+            I64: key,
+            S: host,
+            U16: port,
+            U32: size,
+            U64: hash,
+            F64: ratio,
+            B: route,
+            ..ColferTypes::default()
+        })
+        .collect()
+}
+
+fn text_heavy(texts: &[String]) -> Vec<ColferTypes<'_>> {
+    vec![ColferTypes {
+        S: &texts[0],
+        Ss: texts.iter().map(|s| s.as_str()).collect(),
+        ..ColferTypes::default()
+    }]
+}
+
+fn list_heavy(blobs: &[Vec<u8>]) -> Vec<ColferTypes<'_>> {
+    vec![ColferTypes {
+        F32s: (0..10_000).map(|i| i as f32 * 0.5).collect(),
+        F64s: (0..1_000).map(|i| i as f64 * 0.25).collect(),
+        As: blobs.iter().map(|a| a.as_slice()).collect(),
+        ..ColferTypes::default()
+    }]
+}
+
+fn deeply_nested() -> Vec<ColferTypes<'static>> {
+    fn chain(depth: usize) -> ColferTypes<'static> {
+        ColferTypes {
+            U32: depth as u32,
+            S: "node",
+            O: if depth > 0 { Some(Box::new(chain(depth - 1))) } else { None },
+            ..ColferTypes::default()
+        }
+    }
+
+    let mut o = chain(32);
+    o.Os = (0..100).map(|i| Some(chain(i % 4))).collect();
+    vec![o]
+}
+
+fn bench_shape(c: &mut Criterion, name: &str, data: &[ColferTypes]) {
+    let encoded: Vec<Vec<u8>> = data.iter().map(|o| o.colf_marshal_binary().unwrap()).collect();
+    let bytes = encoded.iter().map(|e| e.len()).sum::<usize>() / encoded.len();
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(bytes as u64));
+
+    let mut i = 0;
+    report_allocs(&format!("{}/marshal", name), || {
+        black_box(data[i % data.len()].colf_marshal_binary().unwrap());
+        i += 1;
+    });
+    group.bench_function("marshal", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i += 1;
+            black_box(data[i % data.len()].colf_marshal_binary().unwrap())
+        })
+    });
+
+    let mut buf = Vec::new();
+    report_allocs(&format!("{}/marshal_reuse", name), || {
+        buf.clear();
+        black_box(data[i % data.len()].colf_marshal_to(&mut buf));
+        i += 1;
+    });
+    group.bench_function("marshal_reuse", |b| {
+        let mut i = 0;
+        let mut buf = Vec::new();
+        b.iter(|| {
+            i += 1;
+            buf.clear();
+            black_box(data[i % data.len()].colf_marshal_to(&mut buf))
+        })
+    });
+
+    report_allocs(&format!("{}/unmarshal", name), || {
+        let mut o = ColferTypes::default();
+        black_box(o.colf_unmarshal(&encoded[i % encoded.len()]).unwrap());
+        i += 1;
+    });
+    group.bench_function("unmarshal", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i += 1;
+            let mut o = ColferTypes::default();
+            o.colf_unmarshal(&encoded[i % encoded.len()]).unwrap();
+            black_box(o)
+        })
+    });
+
+    let mut o = ColferTypes::default();
+    report_allocs(&format!("{}/unmarshal_reuse", name), || {
+        black_box(o.colf_unmarshal_into(&encoded[i % encoded.len()]).unwrap());
+        i += 1;
+    });
+    group.bench_function("unmarshal_reuse", |b| {
+        let mut i = 0;
+        let mut o = ColferTypes::default();
+        b.iter(|| {
+            i += 1;
+            black_box(o.colf_unmarshal_into(&encoded[i % encoded.len()]).unwrap())
+        })
+    });
+
+    #[cfg(feature = "arena")]
+    {
+        let mut arena = Bump::new();
+        report_allocs(&format!("{}/unmarshal_arena", name), || {
+            arena.reset();
+            let mut o = ColferTypesArena::new_in(&arena);
+            black_box(o.colf_unmarshal(&encoded[i % encoded.len()]).unwrap());
+            i += 1;
+        });
+        group.bench_function("unmarshal_arena", |b| {
+            let mut i = 0;
+            let mut arena = Bump::new();
+            b.iter(|| {
+                i += 1;
+                arena.reset();
+                let mut o = ColferTypesArena::new_in(&arena);
+                black_box(o.colf_unmarshal(&encoded[i % encoded.len()]).unwrap())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmarks(c: &mut Criterion) {
+    bench_shape(c, "small", &small());
+
+    let texts: Vec<String> = (0..100).map(|i| format!("text element number {} of the list", i)).collect();
+    bench_shape(c, "text_heavy", &text_heavy(&texts));
+
+    let blobs: Vec<Vec<u8>> = (0..100).map(|i| vec![i as u8; 256]).collect();
+    bench_shape(c, "list_heavy", &list_heavy(&blobs));
+
+    bench_shape(c, "deeply_nested", &deeply_nested());
+}
+
+criterion_group!(benches, benchmarks);
+criterion_main!(benches);
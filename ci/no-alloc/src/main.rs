@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+extern crate colfer_rs;
+
+use colfer_rs::{ColferSerializable, ColferTypesHeapless};
+use core::alloc::{GlobalAlloc, Layout};
+use core::hint::black_box;
+use core::panic::PanicInfo;
+use core::ptr;
+
+// U32 = 5, S = "hi", Ss = ["a"], I64s = [-1].
+static MESSAGE: [u8; 14] = [0x01, 0x05, 0x08, 0x02, b'h', b'i', 0x0C, 0x01, 0x01, b'a', 0x13, 0x01, 0x01, 0x7F];
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let mut o = ColferTypesHeapless::<8, 4>::default();
+    let mut out = [0; 64];
+    if let Ok(n) = o.colf_unmarshal_binary(black_box(&MESSAGE[..])) {
+        let _ = black_box(o.colf_marshal_into(&mut out).map(|l| l == n));
+    }
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
+
+// The bytes crate links `alloc` for types colfer-rs doesn't use without it, so linking
+// needs an allocator. CI checks that no code of `alloc` ends up in the binary.
+struct NoAlloc;
+
+unsafe impl GlobalAlloc for NoAlloc {
+    unsafe fn alloc(&self, _layout: Layout) -> *mut u8 {
+        ptr::null_mut()
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[global_allocator]
+static ALLOCATOR: NoAlloc = NoAlloc;
@@ -0,0 +1,141 @@
+extern crate colfer_rs;
+
+use colfer_rs::codegen;
+use colfer_rs::compat;
+use colfer_rs::lint;
+use colfer_rs::schema::{self, Schema, SchemaError};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "usage: colf gen [-o dir] [-t package.type.field=type]... file.colf...
+       colf compat old.colf... -- new.colf...
+       colf fmt [-w] file.colf...
+       colf lint file.colf...
+
+commands:
+  gen       generates a Rust module per package, into dir or the working directory,
+            with fields of the Rust types given by -t
+  compat    lists the changes from the old to the new schema, failing on breaking ones
+  fmt       prints the files formatted the canonical way, or rewrites them with -w
+  lint      lists the problems of the files, failing on any";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("gen") => gen(&args[1..]),
+        Some("compat") => compat(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("lint") => lint(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(msg) = result {
+        eprintln!("colf: {}", msg);
+        process::exit(1);
+    }
+}
+
+fn gen(args: &[String]) -> Result<(), String> {
+    let mut out = PathBuf::from(".");
+    let mut config = codegen::Config::default();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-o" {
+            out = args.next().ok_or("-o needs a directory")?.into();
+        } else if arg == "-t" {
+            let mapping = args.next().ok_or("-t needs a package.type.field=type mapping")?;
+            let (field, rust) = mapping.split_once('=').ok_or_else(|| format!("-t {}: no =type", mapping))?;
+            config.types.insert(field.to_string(), rust.to_string());
+        } else {
+            files.push(arg.as_str());
+        }
+    }
+
+    let schema = load(&files)?;
+    let modules = codegen::generate_with(&schema, &config).map_err(|e| describe(None, &e))?;
+    for m in modules {
+        let path = out.join(format!("{}.rs", m.name));
+        fs::write(&path, m.source).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn compat(args: &[String]) -> Result<(), String> {
+    let split = args.iter().position(|a| a == "--").ok_or("compat needs -- between the old and the new files")?;
+    let old: Vec<&str> = args[..split].iter().map(|s| s.as_str()).collect();
+    let new: Vec<&str> = args[split + 1..].iter().map(|s| s.as_str()).collect();
+
+    let changes = compat::check_compat(&load(&old)?, &load(&new)?).map_err(|e| describe(None, &e))?;
+    for c in changes.iter() {
+        println!("{}", c);
+    }
+    match changes.iter().filter(|c| c.is_breaking()).count() {
+        0 => Ok(()),
+        n => Err(format!("{} breaking changes", n)),
+    }
+}
+
+fn fmt(args: &[String]) -> Result<(), String> {
+    let write = args.first().is_some_and(|a| a == "-w");
+    let files = if write { &args[1..] } else { args };
+    if files.is_empty() {
+        return Err("no schema files given".to_string());
+    }
+    for file in files {
+        let src = fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+        let pkg = schema::parse(&src).map_err(|e| describe(Some(Path::new(file)), &e))?;
+        let formatted = schema::format(&pkg);
+        if !write {
+            print!("{}", formatted);
+        } else if formatted != src {
+            fs::write(file, formatted).map_err(|e| format!("{}: {}", file, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn lint(args: &[String]) -> Result<(), String> {
+    if args.is_empty() {
+        return Err("no schema files given".to_string());
+    }
+    let mut n = 0;
+    for file in args {
+        let src = fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+        let pkg = schema::parse(&src).map_err(|e| describe(Some(Path::new(file)), &e))?;
+        for l in lint::lint(&pkg) {
+            println!("{}:{}: {}", file, l.line, l.msg);
+            n += 1;
+        }
+    }
+    match n {
+        0 => Ok(()),
+        n => Err(format!("{} problems", n)),
+    }
+}
+
+// Parses the files into one schema.
+fn load(files: &[&str]) -> Result<Schema, String> {
+    if files.is_empty() {
+        return Err("no schema files given".to_string());
+    }
+    let mut schema = Schema::new();
+    for &file in files {
+        let src = fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+        let pkg = schema::parse(&src).map_err(|e| describe(Some(Path::new(file)), &e))?;
+        schema.add(pkg).map_err(|e| describe(Some(Path::new(file)), &e))?;
+    }
+    Ok(schema)
+}
+
+fn describe(file: Option<&Path>, e: &SchemaError) -> String {
+    match file {
+        Some(file) if e.line != 0 => format!("{}:{}: {}", file.display(), e.line, e.msg),
+        None if e.line != 0 => format!("line {}: {}", e.line, e.msg),
+        _ => e.msg.clone(),
+    }
+}
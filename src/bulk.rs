@@ -0,0 +1,228 @@
+//! Bulk decoding of list payloads. Float lists are byte-swapped 16 bytes at a time with
+//! SSSE3 shuffles on x86_64 CPUs that have them, and one value at a time elsewhere. Text
+//! and binary lists are split in a single pass, with the UTF-8 of each text checked as
+//! it is split off.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::str;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_setr_epi8, _mm_shuffle_epi8, _mm_storeu_si128};
+
+use super::{error::{ColferError, ColferResult}, varint, COLFER_SIZE_MAX};
+
+/// Appends the big-endian `float32` values of `data` to `out`. Trailing bytes that don't
+/// make a whole value are ignored.
+#[cfg(feature = "alloc")]
+pub fn extend_f32s(out: &mut Vec<f32>, data: &[u8]) {
+    let n = data.len() / 4;
+    out.reserve(n);
+    let len = out.len();
+    // Safe as `out` has room for `n` more values, all of which get written.
+    unsafe {
+        write_f32s(out.as_mut_ptr().add(len), &data[..n * 4]);
+        out.set_len(len + n);
+    }
+}
+
+/// Appends the big-endian `float64` values of `data` to `out`. Trailing bytes that don't
+/// make a whole value are ignored.
+#[cfg(feature = "alloc")]
+pub fn extend_f64s(out: &mut Vec<f64>, data: &[u8]) {
+    let n = data.len() / 8;
+    out.reserve(n);
+    let len = out.len();
+    // Safe as `out` has room for `n` more values, all of which get written.
+    unsafe {
+        write_f64s(out.as_mut_ptr().add(len), &data[..n * 8]);
+        out.set_len(len + n);
+    }
+}
+
+/// Sets `out` to the big-endian `float32` values at the start of `data`.
+///
+/// Panics when `data` is shorter than 4 bytes per element of `out`.
+pub fn copy_f32s(out: &mut [f32], data: &[u8]) {
+    let data = &data[..out.len() * 4];
+    // Safe as `out` has room for all values in `data`.
+    unsafe { write_f32s(out.as_mut_ptr(), data) }
+}
+
+/// Sets `out` to the big-endian `float64` values at the start of `data`.
+///
+/// Panics when `data` is shorter than 8 bytes per element of `out`.
+pub fn copy_f64s(out: &mut [f64], data: &[u8]) {
+    let data = &data[..out.len() * 8];
+    // Safe as `out` has room for all values in `data`.
+    unsafe { write_f64s(out.as_mut_ptr(), data) }
+}
+
+// Writes the values of `data`, which holds a whole number of them, to `dst`.
+unsafe fn write_f32s(dst: *mut f32, data: &[u8]) {
+    let dst = dst as *mut u8;
+    let done = swap_bytes(data, dst, 4);
+    for i in (done..data.len()).step_by(4) {
+        let x = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        (dst.add(i) as *mut u32).write_unaligned(x);
+    }
+}
+
+unsafe fn write_f64s(dst: *mut f64, data: &[u8]) {
+    let dst = dst as *mut u8;
+    let done = swap_bytes(data, dst, 8);
+    for i in (done..data.len()).step_by(8) {
+        let mut a = [0; 8];
+        a.copy_from_slice(&data[i..i + 8]);
+        (dst.add(i) as *mut u64).write_unaligned(u64::from_be_bytes(a));
+    }
+}
+
+// Converts the leading 16-byte blocks of `src` from big-endian values of `width` bytes
+// to native ones at `dst`, and returns the number of bytes done. The caller handles the
+// rest, which is everything when no SIMD path applies.
+#[cfg(target_arch = "x86_64")]
+unsafe fn swap_bytes(src: &[u8], dst: *mut u8, width: usize) -> usize {
+    if has_ssse3() {
+        swap_bytes_ssse3(src, dst, width)
+    } else {
+        0
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+fn has_ssse3() -> bool {
+    std::is_x86_feature_detected!("ssse3")
+}
+
+// Without std there is no runtime detection, so only what the target guarantees counts.
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+fn has_ssse3() -> bool {
+    cfg!(target_feature = "ssse3")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn swap_bytes(_src: &[u8], _dst: *mut u8, _width: usize) -> usize {
+    0
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_bytes_ssse3(src: &[u8], dst: *mut u8, width: usize) -> usize {
+    let mask = if width == 4 {
+        _mm_setr_epi8(3, 2, 1, 0, 7, 6, 5, 4, 11, 10, 9, 8, 15, 14, 13, 12)
+    } else {
+        _mm_setr_epi8(7, 6, 5, 4, 3, 2, 1, 0, 15, 14, 13, 12, 11, 10, 9, 8)
+    };
+
+    let mut i = 0;
+    while i + 16 <= src.len() {
+        let v = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+        _mm_storeu_si128(dst.add(i) as *mut __m128i, _mm_shuffle_epi8(v, mask));
+        i += 16;
+    }
+    i
+}
+
+/// Splits the `count` length-prefixed binaries at `data[start..]` into `out` and returns
+/// the number of bytes read. With `canonical` set, length prefixes must be minimal
+/// varints. Errors report their byte offset in `data`.
+pub fn split_binaries<'a, E>(data: &'a [u8], start: usize, count: usize, field: &'static str, canonical: bool, out: &mut E) -> ColferResult<usize>
+where
+    E: Extend<&'a [u8]>,
+{
+    walk(data, start, count, field, canonical, |a, _| {
+        out.extend(Some(a));
+        Ok(())
+    })
+}
+
+/// Splits the `count` length-prefixed texts at `data[start..]` into `out` and returns the
+/// number of bytes read. With `canonical` set, length prefixes must be minimal varints.
+/// Errors report their byte offset in `data`.
+pub fn split_texts<'a, E>(data: &'a [u8], start: usize, count: usize, field: &'static str, canonical: bool, out: &mut E) -> ColferResult<usize>
+where
+    E: Extend<&'a str>,
+{
+    walk(data, start, count, field, canonical, |a, byte| {
+        out.extend(Some(str::from_utf8(a).map_err(|_| ColferError::InvalidUtf8 { byte })?));
+        Ok(())
+    })
+}
+
+// Calls `f` with each of the `count` length-prefixed payloads at `data[start..]`, and
+// the offset of the payload in `data`, and returns the number of bytes read.
+fn walk<'a, F>(data: &'a [u8], start: usize, count: usize, field: &'static str, canonical: bool, mut f: F) -> ColferResult<usize>
+where
+    F: FnMut(&'a [u8], usize) -> ColferResult<()>,
+{
+    let mut i = start;
+    for _ in 0..count {
+        let (x, n) = match data.get(i) {
+            Some(&b) if b < 0x80 => (b as usize, 1),
+            _ => varint::decode_usize(data.get(i..).unwrap_or(&[]))?,
+        };
+        canonical_guard!(canonical, n == varint::size_usize(x), NonMinimalVarint, i);
+        if x > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field,
+                overflow: x - COLFER_SIZE_MAX,
+            });
+        }
+
+        let at = i + n;
+        if data.len() - at < x {
+            return Err(ColferError::UnexpectedEof);
+        }
+        f(&data[at..at + x], at)?;
+        i = at + x;
+    }
+    Ok(i - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_from_offset() {
+        let data = [0xAA, 0xBB, 2, b'h', b'i', 0, 1, b'!', 0x7F];
+        let mut a = Vec::new();
+        assert_eq!(split_texts(&data, 2, 3, "", true, &mut a).unwrap(), 6);
+        assert_eq!(a, ["hi", "", "!"]);
+    }
+
+    #[test]
+    fn utf8_error_byte() {
+        let data = [0xAA, 0xBB, 1, b'a', 2, 0xC3, 0x28];
+        let mut a = Vec::new();
+        let r = split_texts(&data, 2, 2, "", false, &mut a);
+        assert!(matches!(r, Err(ColferError::InvalidUtf8 { byte: 5 })), "{:?}", r);
+    }
+
+    #[test]
+    fn varint_error_byte() {
+        let data = [0xAA, 0xBB, 1, b'a', 0x81, 0x00, b'b'];
+        let mut a = Vec::new();
+        let r = split_binaries(&data, 2, 2, "", true, &mut a);
+        assert!(matches!(r, Err(ColferError::NonMinimalVarint { byte: 4 })), "{:?}", r);
+        a.clear();
+        assert_eq!(split_binaries(&data, 2, 2, "", false, &mut a).unwrap(), 5);
+        assert_eq!(a, [&b"a"[..], &b"b"[..]]);
+    }
+
+    #[test]
+    fn swap_floats() {
+        let x: Vec<f64> = (0..7).map(|i| i as f64 * 1.5 - 3.0).collect();
+        let data: Vec<u8> = x.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let mut a = Vec::new();
+        extend_f64s(&mut a, &data);
+        assert_eq!(a, x);
+
+        let y: Vec<f32> = x.iter().map(|&v| v as f32).collect();
+        let data: Vec<u8> = y.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let mut b = [0f32; 7];
+        copy_f32s(&mut b, &data);
+        assert_eq!(b[..], y[..]);
+    }
+}
@@ -0,0 +1,1261 @@
+//! Generates Rust code from a `Schema`, one module per package. Each data structure gets
+//! a struct with `ColferSerializable`, a descriptor and a `*View` type, just like the
+//! built-in `ColferTypes`.
+//!
+//! The modules of the packages compiled together are meant to be siblings, e.g. saved as
+//! `demo.rs` and `people.rs` next to each other and declared with `mod demo;` and
+//! `mod people;`, as references across packages go through `super::`. They need the
+//! `colfer_rs` crate and nothing else.
+//!
+//! A `Config` can give fields a Rust type of their own instead, such as `uuid::Uuid` for a
+//! `binary` or an enum for a `uint8`, which the generated code converts from and to the
+//! encoded type with the standard traits. Any mismatch is thus a compile error of the
+//! generated code. Fields with a `colfer:enum` annotation get a generated enum that way.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::schema::{Enum, Field, FieldType, Package, Schema, SchemaError, SchemaResult, Struct};
+
+/// Generated source of one package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    /// Name of the module, which is the package name unless that is a Rust keyword.
+    pub name: String,
+    pub source: String,
+}
+
+/// Options of `generate_with`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Rust types of fields in place of the default ones, by `package.type.field`. Lists
+    /// and data structures keep their types. The mapped type must implement `Debug`,
+    /// `Clone`, `Default` and `PartialEq`, and convert as follows, where a failing
+    /// `TryFrom` makes decoding fail with `ColferError::InvalidValue`:
+    ///
+    /// * `text`: `TryFrom<&'a str>` and `AsRef<str>`, e.g. `Arc<str>`
+    /// * `binary`: `TryFrom<&'a [u8]>` and `AsRef<[u8]>`, e.g. `[u8; 32]` or `uuid::Uuid`
+    /// * any other type: `Copy` and `TryFrom` of its Rust type, which must implement
+    ///   `From` of the mapped type, e.g. an enum with `TryFrom<u8>` for a `uint8`
+    ///
+    /// Views keep returning the encoded types.
+    pub types: BTreeMap<String, String>,
+}
+
+/// Generates the modules of all packages in `schema`, after resolving it.
+pub fn generate(schema: &Schema) -> SchemaResult<Vec<Module>> {
+    generate_with(schema, &Config::default())
+}
+
+/// Generates like `generate`, with the types of `config`.
+pub fn generate_with(schema: &Schema, config: &Config) -> SchemaResult<Vec<Module>> {
+    schema.resolve()?;
+    for (key, rust) in config.types.iter() {
+        let mut path = key.splitn(3, '.');
+        let field = match (path.next(), path.next(), path.next()) {
+            (Some(pkg), Some(t), Some(field)) => schema.package(pkg).and_then(|p| p.find(t)).and_then(|t| t.fields.iter().find(|f| f.name == field)),
+            _ => None,
+        };
+        match field {
+            None => return Err(SchemaError::new(0, format!("type {} for {}: no such field", rust, key))),
+            Some(f) if f.list => return Err(SchemaError::new(f.line, format!("type {} for {}: lists can't be mapped", rust, key))),
+            Some(f) if matches!(f.ty, FieldType::Struct(_)) => {
+                return Err(SchemaError::new(f.line, format!("type {} for {}: data structures can't be mapped", rust, key)))
+            }
+            Some(f) if f.enumeration.is_some() => return Err(SchemaError::new(f.line, format!("type {} for {}: field has an enum", rust, key))),
+            Some(_) => {}
+        }
+    }
+    for pkg in schema.packages.iter() {
+        enums(pkg)?;
+        for t in pkg.types.iter() {
+            for (i, f) in t.fields.iter().enumerate() {
+                if let Some(o) = t.fields[..i].iter().find(|o| member_name(&o.name) == member_name(&f.name)) {
+                    return Err(SchemaError::new(
+                        f.line,
+                        format!("field {}.{} is generated as {}, like field {}", t.name, f.name, member_name(&f.name), o.name),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(schema
+        .packages
+        .iter()
+        .map(|pkg| Module {
+            name: ident(&pkg.name),
+            source: Generator { schema, pkg, config }.package(),
+        })
+        .collect())
+}
+
+/// Whether `s` is reserved in any Rust edition, and thus can't name a generated item as is.
+pub fn is_rust_keyword(s: &str) -> bool {
+    const KEYWORDS: [&str; 52] = [
+        "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else", "enum", "extern",
+        "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+        "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+        "virtual", "where", "while", "yield",
+    ];
+    KEYWORDS.contains(&s)
+}
+
+/// Rust identifier for a schema name: keywords get an underscore appended.
+pub fn ident(name: &str) -> String {
+    if is_rust_keyword(name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Rust name of the struct member and view accessor of a field. Like keywords, names
+/// the generated code takes for itself get an underscore appended.
+pub fn member_name(name: &str) -> String {
+    const RESERVED: [&str; 3] = ["unknown_fields", "new", "as_bytes"];
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        ident(name)
+    }
+}
+
+/// Rust type name of a data structure, which is its schema name capitalized like the
+/// exported Go types of upstream Colfer.
+pub fn type_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => ident(&(c.to_ascii_uppercase().to_string() + chars.as_str())),
+        None => String::new(),
+    }
+}
+
+// Name of the descriptor static of a data structure, e.g. COLFER_TYPES for ColferTypes.
+fn static_name(name: &str) -> String {
+    let name = type_name(name);
+    let chars: Vec<char> = name.chars().collect();
+    let mut s = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_lower) {
+                s.push('_');
+            }
+        }
+        s.push(c.to_ascii_uppercase());
+    }
+    s
+}
+
+// Formats a doc comment with the given indent, one /// line per line.
+fn doc_comment(doc: &str, indent: &str) -> String {
+    let mut s = String::new();
+    for line in doc.lines() {
+        if line.is_empty() {
+            let _ = writeln!(s, "{}///", indent);
+        } else {
+            let _ = writeln!(s, "{}/// {}", indent, line);
+        }
+    }
+    s
+}
+
+// Returns an if statement that fails canonical decoding when cond holds.
+fn guard(cond: &str, err: &str) -> String {
+    format!(
+        "                    if canonical && {} {{\n                        return Err(ColferError::{} {{ byte }});\n                    }}\n",
+        cond, err
+    )
+}
+
+// Decode check of an annotated limit, which fails when size x exceeds max.
+fn limit(x: &str, max: Option<usize>, err: &str, label: &str) -> String {
+    match max {
+        None => String::new(),
+        Some(max) => format!(
+            "                    if {x} > {max} {{\n                        return Err(ColferError::{err} {{ field: {label}, overflow: {x} - {max} }});\n                    }}\n",
+            x = x,
+            max = max,
+            err = err,
+            label = label
+        ),
+    }
+}
+
+// Fields that hold nested data structures, which decoding with reuse keeps.
+fn nested(fields: &[Field]) -> impl Iterator<Item = &Field> {
+    fields.iter().filter(|f| matches!(f.ty, FieldType::Struct(_)))
+}
+
+// Position of field i among the nested fields of t.
+fn nested_index(t: &Struct, i: usize) -> usize {
+    nested(&t.fields[..i]).count()
+}
+
+// Start of decoding with reuse: everything is reset, except for the nested data structures.
+fn keep(t: &Struct) -> String {
+    let names: Vec<String> = nested(&t.fields).map(|f| member_name(&f.name)).collect();
+    if names.is_empty() {
+        return "        if reuse {\n            self.colf_reset();\n        }\n".to_string();
+    }
+    let taken: Vec<String> = names.iter().map(|n| format!("mem::take(&mut self.{})", n)).collect();
+    let mut s = format!(
+        "        let mut seen = [false; {}];\n        if reuse {{\n            let kept = ({}{});\n            self.colf_reset();\n",
+        names.len(),
+        taken.join(", "),
+        if names.len() == 1 { "," } else { "" }
+    );
+    for (j, n) in names.iter().enumerate() {
+        let _ = writeln!(s, "            self.{} = kept.{};", n, j);
+    }
+    s.push_str("        }\n");
+    s
+}
+
+// End of decoding with reuse: the nested data structures kept that data had no value for
+// get their reset value after all.
+fn drop_unseen(t: &Struct, indent: &str) -> String {
+    let mut s = String::new();
+    for (j, f) in nested(&t.fields).enumerate() {
+        let clear = if f.list { ".clear()" } else { " = None" };
+        let _ = writeln!(s, "{i}if reuse && !seen[{j}] {{\n{i}    self.{n}{c};\n{i}}}", i = indent, j = j, n = member_name(&f.name), c = clear);
+    }
+    s
+}
+
+// Header of field i with the 0x80 bit set.
+fn flag_header(i: usize) -> String {
+    if i == 0 {
+        "0x80".to_string()
+    } else {
+        format!("{} | 0x80", i)
+    }
+}
+
+// Lists the enums of a package once each, with the field that declares them first. The
+// fields of a package may share an enum by declaring it alike.
+fn enums(pkg: &Package) -> SchemaResult<Vec<(&Struct, &Field, &Enum)>> {
+    let mut enums: Vec<(&Struct, &Field, &Enum)> = Vec::new();
+    for t in pkg.types.iter() {
+        for f in t.fields.iter() {
+            let e = match f.enumeration {
+                Some(ref e) => e,
+                None => continue,
+            };
+            let name = type_name(&e.name);
+            match enums.iter().find(|o| type_name(&o.2.name) == name) {
+                Some(o) if o.1.ty == f.ty && o.2 == e => continue,
+                Some(o) => {
+                    return Err(SchemaError::new(f.line, format!("enum {} of {}.{} differs from the one of {}.{}", name, t.name, f.name, o.0.name, o.1.name)))
+                }
+                None => {}
+            }
+            if pkg.types.iter().any(|o| type_name(&o.name) == name || type_name(&o.name) + "View" == name) {
+                return Err(SchemaError::new(f.line, format!("enum {} of {}.{} has the name of a generated struct", name, t.name, f.name)));
+            }
+            enums.push((t, f, e));
+        }
+    }
+    Ok(enums)
+}
+
+// Rust type of the encoded values of a type other than text, binary or a data structure.
+fn scalar_type(ty: &FieldType) -> &'static str {
+    match *ty {
+        FieldType::Bool => "bool",
+        FieldType::Uint8 => "u8",
+        FieldType::Uint16 => "u16",
+        FieldType::Uint32 => "u32",
+        FieldType::Uint64 => "u64",
+        FieldType::Int32 => "i32",
+        FieldType::Int64 => "i64",
+        FieldType::Float32 => "f32",
+        FieldType::Float64 => "f64",
+        _ => "Timestamp",
+    }
+}
+
+// Enum of field f in data structure t, with the conversions from and to its encoded type.
+fn enumeration(t: &Struct, f: &Field, e: &Enum) -> String {
+    let name = type_name(&e.name);
+    let repr = scalar_type(&f.ty);
+    let mut s = format!("/// Named values of `{}.{}`.\n#[derive(Debug, Clone, Copy", t.name, f.name);
+    let zero = e.values.iter().any(|v| v.1 == 0);
+    if zero {
+        s.push_str(", Default");
+    }
+    let _ = writeln!(s, ", PartialEq, Eq, Hash)]\n#[repr({})]\npub enum {} {{", repr, name);
+    for &(ref v, x) in e.values.iter() {
+        if x == 0 {
+            s.push_str("    #[default]\n");
+        }
+        let _ = writeln!(s, "    {} = {},", type_name(v), x);
+    }
+    if e.keep_unknown {
+        let _ = writeln!(
+            s,
+            "    /// Value without a name in this version of the schema.\n    Unknown({}) = {},",
+            repr,
+            e.unknown_value().unwrap_or(0)
+        );
+    }
+    s.push_str("}\n");
+
+    if !zero {
+        let _ = write!(
+            s,
+            "\nimpl Default for {name} {{\n    fn default() -> Self {{\n        {name}::Unknown(0)\n    }}\n}}\n",
+            name = name
+        );
+    }
+
+    let mut from = String::new();
+    let mut to = String::new();
+    for &(ref v, x) in e.values.iter() {
+        let variant = format!("{}::{}", name, type_name(v));
+        if e.keep_unknown {
+            let _ = writeln!(from, "            {} => {},", x, variant);
+        } else {
+            let _ = writeln!(from, "            {} => Ok({}),", x, variant);
+        }
+        let _ = writeln!(to, "            {} => {},", variant, x);
+    }
+    if e.keep_unknown {
+        let _ = write!(
+            s,
+            "\nimpl From<{repr}> for {name} {{\n    fn from(x: {repr}) -> Self {{\n        match x {{\n{from}            x => {name}::Unknown(x),\n        }}\n    }}\n}}\n",
+            repr = repr,
+            name = name,
+            from = from
+        );
+        let _ = writeln!(to, "            {}::Unknown(x) => x,", name);
+    } else {
+        let _ = write!(
+            s,
+            "\nimpl TryFrom<{repr}> for {name} {{\n    type Error = {repr};\n\n    /// Fails with the value when it has no name.\n    fn try_from(x: {repr}) -> Result<Self, {repr}> {{\n        match x {{\n{from}            x => Err(x),\n        }}\n    }}\n}}\n",
+            repr = repr,
+            name = name,
+            from = from
+        );
+    }
+    let _ = write!(
+        s,
+        "\nimpl From<{name}> for {repr} {{\n    fn from(x: {name}) -> Self {{\n        match x {{\n{to}        }}\n    }}\n}}\n",
+        repr = repr,
+        name = name,
+        to = to
+    );
+    s
+}
+
+struct Generator<'s> {
+    schema: &'s Schema,
+    pkg: &'s Package,
+    config: &'s Config,
+}
+
+impl<'s> Generator<'s> {
+    fn package(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "// Code generated by colf from package {}. DO NOT EDIT.", self.pkg.name);
+        for line in self.pkg.doc.lines() {
+            let _ = writeln!(s, "//! {}", line);
+        }
+        s.push_str(
+            "#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+",
+        );
+        for (t, f, e) in enums(self.pkg).expect("checked enums") {
+            s.push('\n');
+            s.push_str(&enumeration(t, f, e));
+        }
+        for t in self.pkg.types.iter() {
+            s.push('\n');
+            s.push_str(&self.data_struct(t));
+        }
+        s
+    }
+
+    // Path of the data structure a field refers to, prefixed by its module when in
+    // another package, with the Rust name transformed by f.
+    fn path<F: Fn(&str) -> String>(&self, field: &Field, f: F) -> String {
+        match field.ty {
+            FieldType::Struct(ref r) => {
+                let (pkg, t) = self.schema.lookup(&self.pkg.name, r).expect("resolved schema");
+                if pkg.name == self.pkg.name {
+                    f(&t.name)
+                } else {
+                    format!("super::{}::{}", ident(&pkg.name), f(&t.name))
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Rust type of a field mapped by the configuration or an enum, if any.
+    fn mapped(&self, t: &Struct, f: &Field) -> Option<String> {
+        if let Some(ref e) = f.enumeration {
+            return Some(type_name(&e.name));
+        }
+        let key = format!("{}.{}.{}", self.pkg.name, t.name, f.name);
+        self.config.types.get(&key).cloned()
+    }
+
+    // Expression of the field value as encoded, converted when mapped.
+    fn value(&self, t: &Struct, f: &Field) -> String {
+        let n = member_name(&f.name);
+        match (self.mapped(t, f), &f.ty) {
+            (None, _) => format!("self.{}", n),
+            (Some(_), &FieldType::Text) => format!("AsRef::<str>::as_ref(&self.{})", n),
+            (Some(_), &FieldType::Binary) => format!("AsRef::<[u8]>::as_ref(&self.{})", n),
+            (Some(_), ty) => format!("{}::from(self.{})", scalar_type(ty), n),
+        }
+    }
+
+    // Statement that sets the field to decoded value x, converted when mapped.
+    fn assign(&self, t: &Struct, f: &Field, x: &str) -> String {
+        let n = member_name(&f.name);
+        match self.mapped(t, f) {
+            None => format!("                    self.{} = {};\n", n, x),
+            Some(_) => format!(
+                "                    self.{} = TryFrom::try_from({}).map_err(|_| ColferError::InvalidValue {{ byte }})?;\n",
+                n, x
+            ),
+        }
+    }
+
+    fn rust_type(&self, t: &Struct, field: &Field) -> String {
+        if let Some(rust) = self.mapped(t, field) {
+            return rust;
+        }
+        let elem = match field.ty {
+            FieldType::Text => "&'a str".to_string(),
+            FieldType::Binary if field.list => "&'a [u8]".to_string(),
+            FieldType::Binary => return "Vec<u8>".to_string(),
+            FieldType::Struct(_) => {
+                let t = format!("{}<'a>", self.path(field, type_name));
+                if !field.list {
+                    return format!("Option<Box<{}>>", t);
+                }
+                format!("Option<{}>", t)
+            }
+            ref ty => scalar_type(ty).to_string(),
+        };
+        if field.list {
+            format!("Vec<{}>", elem)
+        } else {
+            elem
+        }
+    }
+
+    fn data_struct(&self, t: &Struct) -> String {
+        let name = type_name(&t.name);
+        let desc = static_name(&t.name);
+        let mut s = String::new();
+
+        s.push_str(&doc_comment(&t.doc, ""));
+        let _ = writeln!(s, "#[derive(Debug, Clone, Default, PartialEq)]\npub struct {}<'a> {{", name);
+        for f in t.fields.iter() {
+            s.push_str(&doc_comment(&f.doc_text(), "    "));
+            let _ = writeln!(s, "    pub {}: {},", member_name(&f.name), self.rust_type(t, f));
+        }
+        s.push_str(
+            "    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+",
+        );
+
+        let _ = write!(
+            s,
+            "\npub static {}: MessageDescriptor = MessageDescriptor {{\n    name: {:?},\n    doc: {:?},\n    fields: &[\n",
+            desc, t.name, t.doc
+        );
+        for (i, f) in t.fields.iter().enumerate() {
+            let ty = match f.ty {
+                FieldType::Struct(_) => format!("ColferType::Struct(&{})", self.path(f, static_name)),
+                ref ty => format!("ColferType::{:?}", ty),
+            };
+            let _ = write!(
+                s,
+                "        FieldDescriptor {{\n            name: {:?},\n            index: {},\n            ty: {},\n            list: {},\n            doc: {:?},\n        }},\n",
+                f.name, i, ty, f.list, f.doc_text()
+            );
+        }
+        s.push_str("    ],\n};\n");
+
+        let _ = write!(
+            s,
+            "
+impl<'a> ColferSerializable<'a> for {name}<'a> {{
+    const DESCRIPTOR: &'static MessageDescriptor = &{desc};
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {{
+        let remaining = buf.remaining_mut();
+{marshal}
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }}
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {{
+        let mut l = 1 + self.unknown_fields.len();
+{len}
+        if l > COLFER_SIZE_MAX {{
+            return Err(ColferError::MaxSizeBreach {{
+                field: \"self\",
+                overflow: l - COLFER_SIZE_MAX,
+            }});
+        }}
+
+        Ok(l)
+    }}
+
+    fn colf_reset(&mut self) {{
+{reset}        self.unknown_fields = &[];
+    }}
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {{
+        self.colf_decode(data, opts, false)
+    }}
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {{
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }}
+}}
+
+impl<'a> {name}<'a> {{
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {{
+        let len = data.len();
+        if len == 0 {{
+            return Err(ColferError::UnexpectedEof);
+        }}
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+{keep}
+        loop {{
+            if !buf.has_remaining() {{
+                return Err(ColferError::UnexpectedEof);
+            }}
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {{
+                let index = header & 0x7F;
+                match last_index {{
+                    Some(last) if index == last => {{
+                        return Err(ColferError::DuplicateField {{ byte }});
+                    }}
+                    Some(last) if index < last => {{
+                        return Err(ColferError::HeaderOrder {{ byte }});
+                    }}
+                    _ => last_index = Some(index),
+                }}
+            }}
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {{
+                if let Some(field) = {desc}.field(header & 0x7F) {{
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }}
+            }}
+
+            match header {{
+{unmarshal}                0x7F => {{
+{drop_inner}                    return Ok(buf.position());
+                }}
+                _ => {{
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= {desc}.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {{
+                        self.unknown_fields = &data[byte..len - 1];
+{drop_outer}                        return Ok(len);
+                    }}
+                    return Err(ColferError::UnknownHeader {{ byte }});
+                }}
+            }}
+        }}
+    }}
+}}
+",
+            name = name,
+            desc = desc,
+            marshal = t.fields.iter().enumerate().map(|(i, f)| self.marshal(t, i, f)).collect::<Vec<_>>().join("\n"),
+            len = t.fields.iter().map(|f| self.len(t, f)).collect::<String>(),
+            reset = t.fields.iter().map(|f| self.reset(t, f)).collect::<String>(),
+            unmarshal = t.fields.iter().enumerate().map(|(i, f)| self.unmarshal(t, i, f)).collect::<String>(),
+            keep = keep(t),
+            drop_inner = drop_unseen(t, "                    "),
+            drop_outer = drop_unseen(t, "                        "),
+        );
+
+        s.push_str(&self.view(t));
+        s
+    }
+
+    fn marshal(&self, t: &Struct, i: usize, f: &Field) -> String {
+        let n = member_name(&f.name);
+        let (v, mapped) = (self.value(t, f), self.mapped(t, f).is_some());
+        if f.list {
+            let elem = match f.ty {
+                FieldType::Float32 => "buf.put_u32(x.to_bits());".to_string(),
+                FieldType::Float64 => "buf.put_u64(x.to_bits());".to_string(),
+                FieldType::Int32 => "varint::put_u32(buf, varint::zigzag_i32(x));".to_string(),
+                FieldType::Int64 => "varint::put_u64(buf, varint::zigzag_i64(x));".to_string(),
+                FieldType::Text => "varint::put_usize(buf, x.len());\n                buf.put_slice(x.as_bytes());".to_string(),
+                FieldType::Binary => "varint::put_usize(buf, x.len());\n                buf.put_slice(x);".to_string(),
+                _ => {
+                    return format!(
+                        "        if !self.{n}.is_empty() {{
+            buf.put_u8({i});
+            varint::put_usize(buf, self.{n}.len());
+            for o in self.{n}.iter() {{
+                match *o {{
+                    Some(ref o) => {{
+                        o.colf_marshal_to(buf);
+                    }}
+                    None => buf.put_u8(0x7F),
+                }}
+            }}
+        }}
+",
+                        n = n,
+                        i = i
+                    )
+                }
+            };
+            let pattern = match f.ty {
+                FieldType::Text | FieldType::Binary => "x",
+                _ => "&x",
+            };
+            return format!(
+                "        if !self.{n}.is_empty() {{
+            buf.put_u8({i});
+            varint::put_usize(buf, self.{n}.len());
+            for {p} in self.{n}.iter() {{
+                {elem}
+            }}
+        }}
+",
+                n = n,
+                i = i,
+                p = pattern,
+                elem = elem
+            );
+        }
+
+        match f.ty {
+            FieldType::Bool => format!("        if {v} {{\n            buf.put_u8({i});\n        }}\n", v = v, i = i),
+            FieldType::Uint8 => format!(
+                "        if {v} != 0 {{\n            buf.put_u8({i});\n            buf.put_u8({v});\n        }}\n",
+                v = v,
+                i = i
+            ),
+            FieldType::Uint16 => format!(
+                "        if {v} >= 1 << 8 {{
+            buf.put_u8({i});
+            buf.put_u16({v});
+        }} else if {v} != 0 {{
+            buf.put_u8({fi});
+            buf.put_u8({v} as u8);
+        }}
+",
+                v = v,
+                i = i,
+                fi = flag_header(i)
+            ),
+            FieldType::Uint32 | FieldType::Uint64 => {
+                let (bits, put) = if f.ty == FieldType::Uint32 { (21, "u32") } else { (49, "u64") };
+                format!(
+                    "        {{
+            let x = {v};
+            if x >= 1 << {bits} {{
+                buf.put_u8({fi});
+                buf.put_{put}(x);
+            }} else if x != 0 {{
+                buf.put_u8({i});
+                varint::put_{put}(buf, x);
+            }}
+        }}
+",
+                    v = v,
+                    i = i,
+                    fi = flag_header(i),
+                    bits = bits,
+                    put = put
+                )
+            }
+            FieldType::Int32 | FieldType::Int64 => format!(
+                "        if {v} != 0 {{
+            if {v} > 0 {{
+                buf.put_u8({i});
+            }} else {{
+                buf.put_u8({fi});
+            }}
+            varint::put_{put}(buf, {v}.unsigned_abs());
+        }}
+",
+                v = v,
+                i = i,
+                fi = flag_header(i),
+                put = if f.ty == FieldType::Int32 { "u32" } else { "u64" }
+            ),
+            FieldType::Float32 | FieldType::Float64 => format!(
+                "        if {v} != 0.0 {{\n            buf.put_u8({i});\n            buf.put_{put}({v}.to_bits());\n        }}\n",
+                v = v,
+                i = i,
+                put = if f.ty == FieldType::Float32 { "u32" } else { "u64" }
+            ),
+            FieldType::Timestamp => format!(
+                "        if !{v}.is_zero() {{
+            // Seconds outside the range of a uint32 take the wide form, as an int64.
+            let s = {v}.seconds;
+            if (0..1 << 32).contains(&s) {{
+                buf.put_u8({i});
+                buf.put_u32(s as u32);
+            }} else {{
+                buf.put_u8({fi});
+                buf.put_i64(s);
+            }}
+
+            buf.put_u32({v}.nanos);
+        }}
+",
+                v = v,
+                i = i,
+                fi = flag_header(i)
+            ),
+            FieldType::Text | FieldType::Binary => format!(
+                "        if !{v}.is_empty() {{
+            buf.put_u8({i});
+            varint::put_usize(buf, {v}.len());
+            buf.put_slice({bytes});
+        }}
+",
+                v = v,
+                i = i,
+                bytes = match f.ty {
+                    FieldType::Text => format!("{}.as_bytes()", v),
+                    _ if mapped => v.clone(),
+                    _ => format!("&{}", v),
+                }
+            ),
+            FieldType::Struct(_) => format!(
+                "        if let Some(ref o) = self.{n} {{\n            buf.put_u8({i});\n            o.colf_marshal_to(buf);\n        }}\n",
+                n = n,
+                i = i
+            ),
+        }
+    }
+
+    fn len(&self, t: &Struct, f: &Field) -> String {
+        let n = member_name(&f.name);
+        let v = self.value(t, f);
+        let size_max = f.size_max.map_or("COLFER_SIZE_MAX".to_string(), |x| x.to_string());
+        let list_max = f.list_max.map_or("COLFER_LIST_MAX".to_string(), |x| x.to_string());
+        if f.list {
+            let max_list = format!(
+                "                if x > {max} {{
+                    return Err(ColferError::MaxListBreach {{
+                        field: \"self::{n}\",
+                        overflow: x - {max},
+                    }});
+                }}
+",
+                n = n,
+                max = list_max
+            );
+            let max_size = format!(
+                "
+                if l > COLFER_SIZE_MAX {{
+                    return Err(ColferError::MaxSizeBreach {{
+                        field: \"self::{n}\",
+                        overflow: l - COLFER_SIZE_MAX,
+                    }});
+                }}
+",
+                n = n
+            );
+            let elems = match f.ty {
+                FieldType::Float32 => " + x * 4;\n".to_string(),
+                FieldType::Float64 => " + x * 8;\n".to_string(),
+                FieldType::Int32 | FieldType::Int64 => {
+                    let (size, zigzag) = if f.ty == FieldType::Int32 { ("u32", "i32") } else { ("u64", "i64") };
+                    format!(
+                        ";\n                for &v in self.{n}.iter() {{\n                    l += varint::size_{size}(varint::zigzag_{zigzag}(v));\n                }}\n",
+                        n = n,
+                        size = size,
+                        zigzag = zigzag
+                    )
+                }
+                FieldType::Text | FieldType::Binary => format!(
+                    ";
+                for a in self.{n}.iter() {{
+                    let xs = a.len();
+                    if xs > {size_max} {{
+                        return Err(ColferError::MaxSizeBreach {{
+                            field: \"self::{n}\",
+                            overflow: xs - {size_max},
+                        }});
+                    }}
+                    l += varint::size_usize(xs) + xs;
+                }}
+{max_size}",
+                    n = n,
+                    size_max = size_max,
+                    max_size = max_size
+                ),
+                _ => format!(
+                    ";
+                for o in self.{n}.iter() {{
+                    match *o {{
+                        Some(ref v) => l += v.colf_marshal_len()?,
+                        None => l += 1,
+                    }}
+                }}
+{max_size}",
+                    n = n,
+                    max_size = max_size
+                ),
+            };
+            return format!(
+                "
+        {{
+            let x = self.{n}.len();
+            if x > 0 {{
+{max_list}                l += 1 + varint::size_usize(x){elems}            }}
+        }}
+",
+                n = n,
+                max_list = max_list,
+                elems = elems
+            );
+        }
+
+        match f.ty {
+            FieldType::Bool => format!("\n        if {} {{\n            l += 1;\n        }}\n", v),
+            FieldType::Uint8 => format!("\n        if {} != 0 {{\n            l += 2;\n        }}\n", v),
+            FieldType::Uint16 => format!(
+                "\n        if {v} >= 1 << 8 {{\n            l += 3;\n        }} else if {v} != 0 {{\n            l += 2;\n        }}\n",
+                v = v
+            ),
+            FieldType::Uint32 | FieldType::Uint64 => {
+                let (bits, fixed, size) = if f.ty == FieldType::Uint32 { (21, 5, "u32") } else { (49, 9, "u64") };
+                format!(
+                    "
+        {{
+            let x = {v};
+            if x >= 1 << {bits} {{
+                l += {fixed};
+            }} else if x != 0 {{
+                l += 1 + varint::size_{size}(x);
+            }}
+        }}
+",
+                    v = v,
+                    bits = bits,
+                    fixed = fixed,
+                    size = size
+                )
+            }
+            FieldType::Int32 | FieldType::Int64 => format!(
+                "\n        if {v} != 0 {{\n            l += 1 + varint::size_{size}({v}.unsigned_abs());\n        }}\n",
+                v = v,
+                size = if f.ty == FieldType::Int32 { "u32" } else { "u64" }
+            ),
+            FieldType::Float32 => format!("\n        if {} != 0.0 {{\n            l += 5;\n        }}\n", v),
+            FieldType::Float64 => format!("\n        if {} != 0.0 {{\n            l += 9;\n        }}\n", v),
+            FieldType::Timestamp => format!(
+                "
+        if !{v}.is_zero() {{
+            if (0..1 << 32).contains(&{v}.seconds) {{
+                l += 9;
+            }} else {{
+                l += 13;
+            }}
+        }}
+",
+                v = v
+            ),
+            FieldType::Text | FieldType::Binary => format!(
+                "
+        {{
+            let x = {v}.len();
+            if x > 0 {{
+                if x > {max} {{
+                    return Err(ColferError::MaxSizeBreach {{
+                        field: \"self::{n}\",
+                        overflow: x - {max},
+                    }});
+                }}
+                l += 1 + varint::size_usize(x) + x;
+            }}
+        }}
+",
+                n = n,
+                v = v,
+                max = size_max
+            ),
+            FieldType::Struct(_) => format!("\n        if let Some(ref v) = self.{} {{\n            l += v.colf_marshal_len()? + 1;\n        }}\n", n),
+        }
+    }
+
+    fn reset(&self, t: &Struct, f: &Field) -> String {
+        let n = member_name(&f.name);
+        let value = match f.ty {
+            _ if self.mapped(t, f).is_some() => "Default::default()",
+            _ if f.list => return format!("        self.{}.clear();\n", n),
+            FieldType::Binary => return format!("        self.{}.clear();\n", n),
+            FieldType::Bool => "false",
+            FieldType::Float32 | FieldType::Float64 => "0.0",
+            FieldType::Timestamp => "Timestamp::UNIX_EPOCH",
+            FieldType::Text => "\"\"",
+            FieldType::Struct(_) => "None",
+            _ => "0",
+        };
+        format!("        self.{} = {};\n", n, value)
+    }
+
+    fn unmarshal(&self, t: &Struct, i: usize, f: &Field) -> String {
+        let n = member_name(&f.name);
+        let label = format!("\"self::{}\"", n);
+        let flagged = format!("                {} => {{\n                    // {} | 0x80\n", i | 0x80, i);
+        let mut s = format!("                {} => {{\n", i);
+
+        if f.list {
+            let _ = write!(
+                s,
+                "                    let l = get_list_len(&mut buf, {}, canonical)?;\n{}{}",
+                label,
+                limit("l", f.list_max, "MaxListBreach", &label),
+                guard("l == 0", "ZeroValue")
+            );
+            match f.ty {
+                FieldType::Float32 | FieldType::Float64 => {
+                    let (width, bits) = if f.ty == FieldType::Float32 { (4, "32") } else { (8, "64") };
+                    let _ = write!(
+                        s,
+                        "                    if buf.remaining() < l * {w} {{
+                        return Err(ColferError::UnexpectedEof);
+                    }}
+                    self.{n}.clear();
+                    let start = buf.position();
+                    bulk::extend_f{bits}s(&mut self.{n}, &data[start..start + l * {w}]);
+                    buf.advance(l * {w});
+",
+                        w = width,
+                        n = n,
+                        bits = bits
+                    );
+                }
+                FieldType::Int32 | FieldType::Int64 => {
+                    let bits = if f.ty == FieldType::Int32 { "32" } else { "64" };
+                    let _ = write!(
+                        s,
+                        "                    self.{n}.clear();
+                    self.{n}.reserve(l);
+                    for _ in 0..l {{
+                        self.{n}.push(varint::unzigzag_i{bits}(get_varint{bits}(&mut buf, canonical)?));
+                    }}
+",
+                        n = n,
+                        bits = bits
+                    );
+                }
+                FieldType::Text | FieldType::Binary => {
+                    let _ = write!(
+                        s,
+                        "                    self.{n}.clear();
+                    self.{n}.reserve(l);
+                    let start = buf.position();
+                    let n = bulk::split_{kind}(data, start, l, {label}, canonical, &mut self.{n})?;
+                    buf.advance(n);
+",
+                        n = n,
+                        kind = if f.ty == FieldType::Text { "texts" } else { "binaries" },
+                        label = label
+                    );
+                    if let Some(max) = f.size_max {
+                        let _ = write!(
+                            s,
+                            "                    if let Some(a) = self.{n}.iter().find(|a| a.len() > {max}) {{
+                        return Err(ColferError::MaxSizeBreach {{ field: {label}, overflow: a.len() - {max} }});
+                    }}
+",
+                            n = n,
+                            max = max,
+                            label = label
+                        );
+                    }
+                }
+                _ => {
+                    // Elements already in the list are decoded into, to keep their allocations.
+                    let _ = write!(
+                        s,
+                        "                    seen[{j}] = true;
+                    self.{n}.truncate(l);
+                    self.{n}.reserve(l - self.{n}.len());
+                    for i in 0..l {{
+                        if i == self.{n}.len() {{
+                            self.{n}.push(None);
+                        }}
+                        let obj = self.{n}[i].get_or_insert_with(Default::default);
+                        let start = buf.position();
+                        let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                        buf.set_position(start + n);
+                    }}
+",
+                        n = n,
+                        j = nested_index(t, i)
+                    );
+                }
+            }
+            s.push_str("                }\n");
+            return s;
+        }
+
+        match f.ty {
+            FieldType::Bool => s.push_str(&self.assign(t, f, "true")),
+            FieldType::Uint8 => {
+                let _ = write!(
+                    s,
+                    "                    let x = get_u8(&mut buf)?;\n{}{}",
+                    guard("x == 0", "ZeroValue"),
+                    self.assign(t, f, "x")
+                );
+            }
+            FieldType::Uint16 => {
+                let _ = write!(
+                    s,
+                    "                    let x = get_u16(&mut buf, false)?;\n{}{assign}                }}\n{flagged}                    let x = get_u16(&mut buf, true)?;\n{}{assign}",
+                    guard("x < 1 << 8", "NonCanonicalForm"),
+                    guard("x == 0", "ZeroValue"),
+                    assign = self.assign(t, f, "x"),
+                    flagged = flagged
+                );
+            }
+            FieldType::Uint32 | FieldType::Uint64 => {
+                let (bits, threshold) = if f.ty == FieldType::Uint32 { ("32", 21) } else { ("64", 49) };
+                let _ = write!(
+                    s,
+                    "                    let x = get_varint{bits}(&mut buf, canonical)?;\n{}{}{assign}                }}\n{flagged}                    let x = get_u{bits}(&mut buf, true)?;\n{}{assign}",
+                    guard("x == 0", "ZeroValue"),
+                    guard(&format!("x >= 1 << {}", threshold), "NonCanonicalForm"),
+                    guard(&format!("x < 1 << {}", threshold), "NonCanonicalForm"),
+                    bits = bits,
+                    assign = self.assign(t, f, "x"),
+                    flagged = flagged
+                );
+            }
+            FieldType::Int32 | FieldType::Int64 => {
+                let (bits, signed) = if f.ty == FieldType::Int32 { ("32", "i32") } else { ("64", "i64") };
+                let _ = write!(
+                    s,
+                    "                    let x = get_varint{bits}(&mut buf, canonical)?;\n{}{}{}                }}\n{flagged}                    let x = get_varint{bits}(&mut buf, canonical)?;\n{}{}{}",
+                    guard("x == 0", "ZeroValue"),
+                    guard(&format!("x > {}::MAX as u{}", signed, bits), "NonCanonicalForm"),
+                    self.assign(t, f, &format!("x as {}", signed)),
+                    guard("x == 0", "ZeroValue"),
+                    guard(&format!("x > 1 << {}", if bits == "32" { 31 } else { 63 }), "NonCanonicalForm"),
+                    self.assign(t, f, &format!("(!x).wrapping_add(1) as {}", signed)),
+                    bits = bits,
+                    flagged = flagged
+                );
+            }
+            FieldType::Float32 | FieldType::Float64 => {
+                let _ = write!(
+                    s,
+                    "                    let x = get_f{}(&mut buf)?;\n{}{}",
+                    if f.ty == FieldType::Float32 { "32" } else { "64" },
+                    guard("x == 0.0", "ZeroValue"),
+                    self.assign(t, f, "x")
+                );
+            }
+            FieldType::Timestamp => {
+                let _ = write!(
+                    s,
+                    "{}                }}\n{}{}",
+                    self.assign(t, f, "get_timestamp(&mut buf, false, canonical)?"),
+                    flagged,
+                    self.assign(t, f, "get_timestamp(&mut buf, true, canonical)?")
+                );
+            }
+            FieldType::Text => {
+                let _ = write!(
+                    s,
+                    "                    let x = get_text(&mut buf, {}, canonical)?;\n{}{}{}",
+                    label,
+                    limit("x.len()", f.size_max, "MaxSizeBreach", &label),
+                    guard("x.is_empty()", "ZeroValue"),
+                    self.assign(t, f, "x")
+                );
+            }
+            FieldType::Binary => {
+                let _ = write!(
+                    s,
+                    "                    let x = get_binary(&mut buf, {}, canonical)?;\n{}{}",
+                    label,
+                    limit("x.len()", f.size_max, "MaxSizeBreach", &label),
+                    guard("x.is_empty()", "ZeroValue")
+                );
+                if self.mapped(t, f).is_some() {
+                    s.push_str(&self.assign(t, f, "x"));
+                } else {
+                    let _ = write!(s, "                    self.{n}.clear();\n                    self.{n}.extend_from_slice(x);\n", n = n);
+                }
+            }
+            FieldType::Struct(_) => {
+                let _ = write!(
+                    s,
+                    "                    seen[{}] = true;
+                    let obj = self.{}.get_or_insert_with(Box::default);
+                    let start = buf.position();
+                    let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                    buf.set_position(start + n);
+",
+                    nested_index(t, i),
+                    n
+                );
+            }
+        }
+        s.push_str("                }\n");
+        s
+    }
+
+    fn view(&self, t: &Struct) -> String {
+        let name = type_name(&t.name);
+        let mut s = format!(
+            "
+/// Lazy, zero-copy view of an encoded `{name}`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct {name}View<'a>(pub(crate) ColferView<'a, {n}>);
+
+impl<'a> {name}View<'a> {{
+    /// Indexes the `{name}` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {{
+        ColferView::new(&{desc}, data).map({name}View)
+    }}
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {{
+        self.0.as_bytes()
+    }}
+",
+            name = name,
+            n = t.fields.len(),
+            desc = static_name(&t.name)
+        );
+
+        for (i, f) in t.fields.iter().enumerate() {
+            let n = member_name(&f.name);
+            let (ret, body) = match (&f.ty, f.list) {
+                (&FieldType::Struct(_), list) => {
+                    let view = self.path(f, |t| type_name(t) + "View");
+                    let desc = self.path(f, static_name);
+                    if list {
+                        (
+                            format!("ColferResult<Vec<{}<'a>>>", view),
+                            format!("Ok(self.0.get_structs({}, &{})?.into_iter().map({}).collect())", i, desc, view),
+                        )
+                    } else {
+                        (
+                            format!("ColferResult<Option<{}<'a>>>", view),
+                            format!("Ok(self.0.get_struct({}, &{})?.map({}))", i, desc, view),
+                        )
+                    }
+                }
+                (&FieldType::Bool, _) => ("bool".to_string(), format!("self.0.get_bool({})", i)),
+                (ty, list) => {
+                    let (ret, getter) = match *ty {
+                        FieldType::Uint8 => ("u8", "u8"),
+                        FieldType::Uint16 => ("u16", "u16"),
+                        FieldType::Uint32 => ("u32", "u32"),
+                        FieldType::Uint64 => ("u64", "u64"),
+                        FieldType::Int32 if list => ("Vec<i32>", "i32s"),
+                        FieldType::Int32 => ("i32", "i32"),
+                        FieldType::Int64 if list => ("Vec<i64>", "i64s"),
+                        FieldType::Int64 => ("i64", "i64"),
+                        FieldType::Float32 if list => ("Vec<f32>", "f32s"),
+                        FieldType::Float32 => ("f32", "f32"),
+                        FieldType::Float64 if list => ("Vec<f64>", "f64s"),
+                        FieldType::Float64 => ("f64", "f64"),
+                        FieldType::Timestamp => ("Timestamp", "timestamp"),
+                        FieldType::Text if list => ("Vec<&'a str>", "texts"),
+                        FieldType::Text => ("&'a str", "text"),
+                        FieldType::Binary if list => ("Vec<&'a [u8]>", "binaries"),
+                        _ => ("&'a [u8]", "binary"),
+                    };
+                    (format!("ColferResult<{}>", ret), format!("self.0.get_{}({})", getter, i))
+                }
+            };
+            let _ = write!(s, "\n    pub fn {}(&self) -> {} {{\n        {}\n    }}\n", n, ret, body);
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::schema::parse;
+
+    // Generates the packages of srcs and checks that the modules under tests/gen, which the
+    // integration tests compile, are what the generator emits now.
+    fn check_current(srcs: &[&str], config: &Config, gen: &[&str]) {
+        let mut schema = Schema::new();
+        for src in srcs.iter() {
+            schema.add(parse(src).unwrap()).unwrap();
+        }
+        let modules = generate_with(&schema, config).unwrap();
+        assert_eq!(modules.len(), gen.len());
+        for (m, want) in modules.iter().zip(gen.iter()) {
+            assert!(m.source == *want, "tests/gen/{}.rs is stale; run colf gen -o tests/gen", m.name);
+        }
+    }
+
+    #[test]
+    fn reuse_module_current() {
+        check_current(&[include_str!("../tests/reuse.colf")], &Config::default(), &[include_str!("../tests/gen/reuse.rs")]);
+    }
+
+    #[test]
+    fn limits_module_current() {
+        check_current(&[include_str!("../tests/limits.colf")], &Config::default(), &[include_str!("../tests/gen/limits.rs")]);
+    }
+
+    #[test]
+    fn enums_module_current() {
+        check_current(&[include_str!("../tests/enums.colf")], &Config::default(), &[include_str!("../tests/gen/enums.rs")]);
+    }
+
+    #[test]
+    fn mapping_module_current() {
+        let mut config = Config::default();
+        config.types.insert(String::from("mapping.record.id"), String::from("[u8; 16]"));
+        check_current(&[include_str!("../tests/mapping.colf")], &config, &[include_str!("../tests/gen/mapping.rs")]);
+    }
+
+    #[test]
+    fn package_modules_current() {
+        check_current(
+            &[include_str!("../tests/forest.colf"), include_str!("../tests/grove.colf")],
+            &Config::default(),
+            &[include_str!("../tests/gen/forest.rs"), include_str!("../tests/gen/grove.rs")],
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Compatibility between two versions of a schema, as seen on the wire. Colfer identifies
+//! fields by their index only, so names may change freely while the index, type and
+//! list-ness of a field must not. Annotations don't change the wire format, but lower
+//! limits and fewer enum values make data of the old version fail to decode.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::schema::{Field, FieldType, Package, Schema, SchemaResult, Struct};
+use super::{COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// What changed between two versions of a data structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// New data structure, which nothing can have sent before.
+    TypeAdded,
+    /// Data structure gone, while peers on the old schema may still send it.
+    TypeRemoved,
+    /// New field after the existing ones. New readers take its absence in old data as the
+    /// zero value. Old readers fail on it when set, unless they preserve unknown fields,
+    /// so the field should only be set once all readers are upgraded.
+    FieldAdded { index: u8, name: String },
+    /// Field gone, while old writers still send it, which fails on new readers.
+    FieldRemoved { index: u8, name: String },
+    /// Same index, type and list-ness under another name, which the wire doesn't carry.
+    FieldRenamed { index: u8, old: String, new: String },
+    /// Field at another index, so peers disagree on which field is which.
+    FieldMoved { name: String, old_index: u8, new_index: u8 },
+    /// Type of the field changed, written as in the schema.
+    FieldTypeChanged { index: u8, name: String, old: String, new: String },
+    /// Field became a list, or stopped being one.
+    ListChanged { index: u8, name: String, list: bool },
+    /// The `size_max` or `list_max` annotation, named by `limit`, changed. Limits without
+    /// annotation are the global ones. Data over a lower limit fails on new readers, and
+    /// data over a higher limit on old readers, so raised limits should only be used once
+    /// all readers are upgraded.
+    LimitChanged {
+        index: u8,
+        name: String,
+        limit: &'static str,
+        old: usize,
+        new: usize,
+    },
+    /// Enum values without a name now fail to decode, as the field got an enum or lost
+    /// `colfer:unknown=keep`.
+    UnknownValuesRejected { index: u8, name: String },
+    /// Values of the enum lost their name, so they fail to decode.
+    EnumValuesRemoved { index: u8, name: String, values: Vec<u16> },
+    /// New values of the enum, which old readers fail on unless they keep unknown values.
+    EnumValuesAdded { index: u8, name: String, values: Vec<u16> },
+}
+
+impl ChangeKind {
+    /// Whether peers on either version can fail to read, or misread, each other's data.
+    pub fn is_breaking(&self) -> bool {
+        match *self {
+            ChangeKind::TypeAdded | ChangeKind::FieldAdded { .. } | ChangeKind::FieldRenamed { .. } | ChangeKind::EnumValuesAdded { .. } => false,
+            ChangeKind::LimitChanged { old, new, .. } => new < old,
+            _ => true,
+        }
+    }
+}
+
+/// Difference found by `check_compat`, in a data structure of the new schema, or of the
+/// old one when removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub package: String,
+    /// Name of the data structure.
+    pub type_name: String,
+    pub kind: ChangeKind,
+}
+
+impl Change {
+    pub fn is_breaking(&self) -> bool {
+        self.kind.is_breaking()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}.{}: ", if self.is_breaking() { "breaking" } else { "safe" }, self.package, self.type_name)?;
+        match self.kind {
+            ChangeKind::TypeAdded => write!(f, "type added"),
+            ChangeKind::TypeRemoved => write!(f, "type removed"),
+            ChangeKind::FieldAdded { index, ref name } => write!(f, "field {} {} added", index, name),
+            ChangeKind::FieldRemoved { index, ref name } => write!(f, "field {} {} removed", index, name),
+            ChangeKind::FieldRenamed { index, ref old, ref new } => write!(f, "field {} renamed from {} to {}", index, old, new),
+            ChangeKind::FieldMoved {
+                ref name,
+                old_index,
+                new_index,
+            } => write!(f, "field {} moved from index {} to {}", name, old_index, new_index),
+            ChangeKind::FieldTypeChanged {
+                index,
+                ref name,
+                ref old,
+                ref new,
+            } => write!(f, "field {} {} changed type from {} to {}", index, name, old, new),
+            ChangeKind::ListChanged { index, ref name, list } => {
+                write!(f, "field {} {} {}", index, name, if list { "became a list" } else { "is no longer a list" })
+            }
+            ChangeKind::LimitChanged {
+                index,
+                ref name,
+                limit,
+                old,
+                new,
+            } => write!(f, "field {} {} changed {} from {} to {}", index, name, limit, old, new),
+            ChangeKind::UnknownValuesRejected { index, ref name } => write!(f, "field {} {} rejects values without a name", index, name),
+            ChangeKind::EnumValuesRemoved { index, ref name, ref values } => write!(f, "field {} {} lost enum values {:?}", index, name, values),
+            ChangeKind::EnumValuesAdded { index, ref name, ref values } => write!(f, "field {} {} got enum values {:?}", index, name, values),
+        }
+    }
+}
+
+/// Lists the differences between the `old` and `new` versions of a schema, breaking or
+/// not, in package and declaration order. Both schemas are resolved first.
+pub fn check_compat(old: &Schema, new: &Schema) -> SchemaResult<Vec<Change>> {
+    old.resolve()?;
+    new.resolve()?;
+
+    let mut changes = Vec::new();
+    for pkg in new.packages.iter() {
+        for t in pkg.types.iter() {
+            match old.package(&pkg.name).and_then(|p| p.find(&t.name)) {
+                Some(old_t) => compare(old_t, t, pkg, &mut changes),
+                None => changes.push(change(pkg, t, ChangeKind::TypeAdded)),
+            }
+        }
+    }
+    for pkg in old.packages.iter() {
+        for t in pkg.types.iter() {
+            if new.package(&pkg.name).and_then(|p| p.find(&t.name)).is_none() {
+                changes.push(change(pkg, t, ChangeKind::TypeRemoved));
+            }
+        }
+    }
+    Ok(changes)
+}
+
+fn change(pkg: &Package, t: &Struct, kind: ChangeKind) -> Change {
+    Change {
+        package: pkg.name.clone(),
+        type_name: t.name.clone(),
+        kind,
+    }
+}
+
+// Compares the fields of two versions of a data structure index by index. Moves are
+// reported at the new index of the field.
+fn compare(old: &Struct, new: &Struct, pkg: &Package, changes: &mut Vec<Change>) {
+    let index_of = |t: &Struct, name: &str| t.fields.iter().position(|f| f.name == name);
+    for i in 0..old.fields.len().max(new.fields.len()) {
+        let index = i as u8;
+        let (o, n) = (old.fields.get(i), new.fields.get(i));
+        let mut push = |kind| changes.push(change(pkg, new, kind));
+
+        let moved = match n {
+            Some(n) if o.is_none_or(|o| o.name != n.name) => index_of(old, &n.name),
+            _ => None,
+        };
+        if let (Some(n), Some(old_index)) = (n, moved) {
+            push(ChangeKind::FieldMoved {
+                name: n.name.clone(),
+                old_index: old_index as u8,
+                new_index: index,
+            });
+        }
+
+        match (o, n) {
+            (Some(o), _) if index_of(new, &o.name).is_none() && (n.is_none() || moved.is_some()) => {
+                push(ChangeKind::FieldRemoved { index, name: o.name.clone() })
+            }
+            (None, Some(n)) if moved.is_none() => push(ChangeKind::FieldAdded { index, name: n.name.clone() }),
+            (Some(o), Some(n)) if moved.is_none() => {
+                let (old_ty, new_ty) = (qualified(&o.ty, &pkg.name), qualified(&n.ty, &pkg.name));
+                if old_ty != new_ty {
+                    push(ChangeKind::FieldTypeChanged {
+                        index,
+                        name: n.name.clone(),
+                        old: old_ty,
+                        new: new_ty,
+                    });
+                } else if o.list != n.list {
+                    push(ChangeKind::ListChanged {
+                        index,
+                        name: n.name.clone(),
+                        list: n.list,
+                    });
+                } else {
+                    if o.name != n.name {
+                        push(ChangeKind::FieldRenamed {
+                            index,
+                            old: o.name.clone(),
+                            new: n.name.clone(),
+                        });
+                    }
+                    annotations(o, n, index, &mut push);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Compares the annotations of two versions of a field with the same type.
+fn annotations<F: FnMut(ChangeKind)>(o: &Field, n: &Field, index: u8, push: &mut F) {
+    let limits = [
+        ("size_max", o.size_max, n.size_max, COLFER_SIZE_MAX),
+        ("list_max", o.list_max, n.list_max, COLFER_LIST_MAX),
+    ];
+    for &(limit, old, new, global) in limits.iter() {
+        let (old, new) = (old.unwrap_or(global), new.unwrap_or(global));
+        if old != new {
+            push(ChangeKind::LimitChanged {
+                index,
+                name: n.name.clone(),
+                limit,
+                old,
+                new,
+            });
+        }
+    }
+
+    // Values that decode, where None is any value.
+    let named = |f: &Field| match f.enumeration {
+        Some(ref e) if !e.keep_unknown => Some(e.values.iter().map(|v| v.1).collect::<Vec<u16>>()),
+        _ => None,
+    };
+    match (named(o), named(n)) {
+        (None, Some(_)) => push(ChangeKind::UnknownValuesRejected { index, name: n.name.clone() }),
+        (Some(old), Some(new)) => {
+            let removed: Vec<u16> = old.iter().copied().filter(|x| !new.contains(x)).collect();
+            if !removed.is_empty() {
+                push(ChangeKind::EnumValuesRemoved {
+                    index,
+                    name: n.name.clone(),
+                    values: removed,
+                });
+            }
+        }
+        _ => {}
+    }
+    let values = |f: &Field| f.enumeration.as_ref().map_or(Vec::new(), |e| e.values.iter().map(|v| v.1).collect());
+    let (old, new) = (values(o), values(n));
+    let added: Vec<u16> = new.iter().copied().filter(|x| !old.contains(x)).collect();
+    if !added.is_empty() && o.enumeration.is_some() {
+        push(ChangeKind::EnumValuesAdded {
+            index,
+            name: n.name.clone(),
+            values: added,
+        });
+    }
+}
+
+// Type of the field, with data structures qualified by their package so that the same
+// name means the same type in both versions.
+fn qualified(ty: &FieldType, pkg: &str) -> String {
+    match *ty {
+        FieldType::Struct(ref r) => format!("{}.{}", r.package_or(pkg), r.name),
+        ref ty => ty.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::schema::parse;
+    use alloc::vec;
+
+    // Changes from the old to the new version of package p, which holds type t with the
+    // given field declarations.
+    fn changes(old: &str, new: &str) -> Vec<ChangeKind> {
+        let schema = |fields: &str| {
+            let mut schema = Schema::new();
+            schema.add(parse(&format!("package p\n\ntype t struct {{\n{}}}\n", fields)).unwrap()).unwrap();
+            schema
+        };
+        check_compat(&schema(old), &schema(new)).unwrap().into_iter().map(|c| c.kind).collect()
+    }
+
+    fn name(s: &str) -> String {
+        String::from(s)
+    }
+
+    #[test]
+    fn field_removed() {
+        let got = changes("\ta text\n\tb int64\n", "\ta text\n");
+        assert_eq!(got, [ChangeKind::FieldRemoved { index: 1, name: name("b") }]);
+        assert!(got[0].is_breaking());
+    }
+
+    #[test]
+    fn field_retyped() {
+        let got = changes("\ta text\n\tb int64\n", "\ta text\n\tb int32\n");
+        assert_eq!(
+            got,
+            [ChangeKind::FieldTypeChanged {
+                index: 1,
+                name: name("b"),
+                old: name("int64"),
+                new: name("int32"),
+            }]
+        );
+        assert!(got[0].is_breaking());
+
+        let got = changes("\ta text\n", "\ta []text\n");
+        assert_eq!(got, [ChangeKind::ListChanged { index: 0, name: name("a"), list: true }]);
+        assert!(got[0].is_breaking());
+    }
+
+    #[test]
+    fn index_reordered() {
+        let got = changes("\ta text\n\tb int64\n", "\tb int64\n\ta text\n");
+        assert_eq!(
+            got,
+            [
+                ChangeKind::FieldMoved {
+                    name: name("b"),
+                    old_index: 1,
+                    new_index: 0,
+                },
+                ChangeKind::FieldMoved {
+                    name: name("a"),
+                    old_index: 0,
+                    new_index: 1,
+                },
+            ]
+        );
+        assert!(got.iter().all(ChangeKind::is_breaking));
+    }
+
+    #[test]
+    fn field_appended() {
+        let got = changes("\ta text\n", "\ta text\n\tb bool\n");
+        assert_eq!(got, [ChangeKind::FieldAdded { index: 1, name: name("b") }]);
+        assert!(!got[0].is_breaking());
+    }
+
+    #[test]
+    fn limits_narrowed() {
+        let limit = |limit, old, new| ChangeKind::LimitChanged {
+            index: 0,
+            name: name("a"),
+            limit,
+            old,
+            new,
+        };
+
+        let got = changes("\ta text // colfer:size_max=64\n", "\ta text // colfer:size_max=32\n");
+        assert_eq!(got, [limit("size_max", 64, 32)]);
+        assert!(got[0].is_breaking());
+
+        let got = changes("\ta []text // colfer:list_max=8\n", "\ta []text // colfer:list_max=4\n");
+        assert_eq!(got, [limit("list_max", 8, 4)]);
+        assert!(got[0].is_breaking());
+
+        // Raised limits only break old readers on data they couldn't have been sent yet.
+        let got = changes("\ta text // colfer:size_max=64\n", "\ta text\n");
+        assert_eq!(got, [limit("size_max", 64, COLFER_SIZE_MAX)]);
+        assert!(!got[0].is_breaking());
+    }
+
+    #[test]
+    fn enum_value_removed() {
+        let got = changes(
+            "\ta uint8 // colfer:enum=Level colfer:values=low,mid,high\n",
+            "\ta uint8 // colfer:enum=Level colfer:values=low,mid\n",
+        );
+        assert_eq!(
+            got,
+            [ChangeKind::EnumValuesRemoved {
+                index: 0,
+                name: name("a"),
+                values: vec![2],
+            }]
+        );
+        assert!(got[0].is_breaking());
+    }
+
+    #[test]
+    fn enum_value_added() {
+        let got = changes(
+            "\ta uint8 // colfer:enum=Level colfer:values=low,mid\n",
+            "\ta uint8 // colfer:enum=Level colfer:values=low,mid,top=9\n",
+        );
+        assert_eq!(
+            got,
+            [ChangeKind::EnumValuesAdded {
+                index: 0,
+                name: name("a"),
+                values: vec![9],
+            }]
+        );
+        assert!(!got[0].is_breaking());
+    }
+}
@@ -0,0 +1,41 @@
+use bytes::Buf;
+
+/// Read position in a byte slice, in place of `std::io::Cursor` which `no_std` builds
+/// don't have.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Cursor { data, pos: 0 }
+    }
+
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+}
+
+impl<'a> Buf for Cursor<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.data.get(self.pos..).unwrap_or(&[])
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.pos += n;
+    }
+}
@@ -0,0 +1,306 @@
+use bytes::Buf;
+
+use super::{cursor::Cursor,
+            descriptor::{ColferType, FieldDescriptor, MessageDescriptor},
+            error::{ColferError, ColferResult},
+            timestamp::Timestamp,
+            varint,
+            COLFER_LIST_MAX,
+            COLFER_SIZE_MAX};
+
+// A varint is minimal when no shorter encoding decodes to the same value.
+pub fn get_varint32(buf: &mut Cursor, canonical: bool) -> ColferResult<u32> {
+    let byte = buf.position();
+    let (x, n) = varint::decode_u32(buf.chunk())?;
+    canonical_guard!(canonical, n == varint::size_u32(x), NonMinimalVarint, byte);
+    buf.advance(n);
+    Ok(x)
+}
+
+pub fn get_varint64(buf: &mut Cursor, canonical: bool) -> ColferResult<u64> {
+    let byte = buf.position();
+    let (x, n) = varint::decode_u64(buf.chunk())?;
+    canonical_guard!(canonical, n == varint::size_u64(x), NonMinimalVarint, byte);
+    buf.advance(n);
+    Ok(x)
+}
+
+pub fn get_list_len(buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize> {
+    let x = get_varint32(buf, canonical)? as usize;
+    if x > COLFER_LIST_MAX {
+        return Err(ColferError::MaxListBreach {
+            field,
+            overflow: x - COLFER_LIST_MAX,
+        });
+    }
+    Ok(x)
+}
+
+pub fn get_binary<'a>(buf: &mut Cursor<'a>, field: &'static str, canonical: bool) -> ColferResult<&'a [u8]> {
+    let x = get_varint32(buf, canonical)? as usize;
+    if x > COLFER_SIZE_MAX {
+        return Err(ColferError::MaxSizeBreach {
+            field,
+            overflow: x - COLFER_SIZE_MAX,
+        });
+    }
+    buf_guard!(buf, x);
+
+    let data: &'a [u8] = buf.get_ref();
+    let start = buf.position();
+    buf.set_position(start + x);
+    Ok(&data[start..start + x])
+}
+
+pub fn get_text<'a>(buf: &mut Cursor<'a>, field: &'static str, canonical: bool) -> ColferResult<&'a str> {
+    let byte = buf.position();
+    let a = get_binary(buf, field, canonical)?;
+    ::core::str::from_utf8(a).map_err(|_| ColferError::InvalidUtf8 { byte })
+}
+
+// Reads a list length like get_list_len, bounded by the capacity N of the list type.
+#[cfg(feature = "heapless")]
+pub fn get_fixed_list_len<const N: usize>(buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize> {
+    let x = get_list_len(buf, field, canonical)?;
+    if x > N {
+        return Err(ColferError::MaxListBreach { field, overflow: x - N });
+    }
+    Ok(x)
+}
+
+// The wide form has 64-bit signed seconds, for anything outside the range of a uint32.
+pub fn get_timestamp(buf: &mut Cursor, wide: bool, canonical: bool) -> ColferResult<Timestamp> {
+    let byte = buf.position() - 1;
+    let s = if wide {
+        buf_guard!(buf, 12);
+        let s = buf.get_i64();
+        canonical_guard!(canonical, !(0..1 << 32).contains(&s), NonCanonicalForm, byte);
+        s
+    } else {
+        buf_guard!(buf, 8);
+        i64::from(buf.get_u32())
+    };
+    let ns = buf.get_u32();
+    canonical_guard!(canonical, s != 0 || ns != 0, ZeroValue, byte);
+    canonical_guard!(canonical, ns < 1_000_000_000, NonCanonicalForm, byte);
+    Ok(Timestamp::new(s, ns))
+}
+
+// The readers below take the 0x80 bit of the header as flag, which selects the
+// alternative encoding of their type.
+
+pub fn get_u8(buf: &mut Cursor) -> ColferResult<u8> {
+    buf_guard!(buf);
+    Ok(buf.get_u8())
+}
+
+pub fn get_u16(buf: &mut Cursor, flag: bool) -> ColferResult<u16> {
+    if flag {
+        buf_guard!(buf);
+        Ok(u16::from(buf.get_u8()))
+    } else {
+        buf_guard!(buf, 2);
+        Ok(buf.get_u16())
+    }
+}
+
+pub fn get_u32(buf: &mut Cursor, flag: bool) -> ColferResult<u32> {
+    if flag {
+        buf_guard!(buf, 4);
+        Ok(buf.get_u32())
+    } else {
+        get_varint32(buf, false)
+    }
+}
+
+pub fn get_u64(buf: &mut Cursor, flag: bool) -> ColferResult<u64> {
+    if flag {
+        buf_guard!(buf, 8);
+        Ok(buf.get_u64())
+    } else {
+        get_varint64(buf, false)
+    }
+}
+
+pub fn get_i32(buf: &mut Cursor, flag: bool) -> ColferResult<i32> {
+    let x = get_varint32(buf, false)?;
+    Ok(if flag { (!x).wrapping_add(1) } else { x } as i32)
+}
+
+pub fn get_i64(buf: &mut Cursor, flag: bool) -> ColferResult<i64> {
+    let x = get_varint64(buf, false)?;
+    Ok(if flag { (!x).wrapping_add(1) } else { x } as i64)
+}
+
+pub fn get_f32(buf: &mut Cursor) -> ColferResult<f32> {
+    buf_guard!(buf, 4);
+    Ok(f32::from_bits(buf.get_u32()))
+}
+
+pub fn get_f64(buf: &mut Cursor) -> ColferResult<f64> {
+    buf_guard!(buf, 8);
+    Ok(f64::from_bits(buf.get_u64()))
+}
+
+/// Walks the headers of the message described by `desc` at the start of `data`, calling
+/// `f` with each field and the offset of its header, and returns the number of bytes of
+/// the message. Payloads are skipped without being decoded.
+pub fn scan_message<F>(desc: &'static MessageDescriptor, data: &[u8], mut f: F) -> ColferResult<usize>
+where
+    F: FnMut(&'static FieldDescriptor, usize),
+{
+    let mut buf = Cursor::new(data);
+    loop {
+        buf_guard!(buf);
+        let header = buf.get_u8();
+        if header == 0x7F {
+            return Ok(buf.position());
+        }
+
+        let byte = buf.position() - 1;
+        let field = match desc.field(header & 0x7F) {
+            Some(field) => field,
+            None => return Err(ColferError::UnknownHeader { byte }),
+        };
+        f(field, byte);
+        skip_field(field, header & 0x80 != 0, &mut buf)?;
+    }
+}
+
+/// Moves `buf` past the payload of `field`, which follows its header.
+pub fn skip_field(field: &FieldDescriptor, flag: bool, buf: &mut Cursor) -> ColferResult<()> {
+    if !field.list {
+        return skip_value(field, flag, buf);
+    }
+    if flag {
+        return Err(ColferError::UnknownHeader {
+            byte: buf.position() - 1,
+        });
+    }
+
+    let l = get_list_len(buf, field.name, false)?;
+    let width = match field.ty {
+        ColferType::Float32 => 4,
+        ColferType::Float64 => 8,
+        _ => {
+            for _ in 0..l {
+                skip_value(field, false, buf)?;
+            }
+            return Ok(());
+        }
+    };
+    buf_guard!(buf, l * width);
+    let pos = buf.position();
+    buf.set_position(pos + l * width);
+    Ok(())
+}
+
+// Moves past one value of the field, or one element for lists.
+fn skip_value(field: &FieldDescriptor, flag: bool, buf: &mut Cursor) -> ColferResult<()> {
+    let width = match (field.ty, flag) {
+        (ColferType::Bool, false) => 0,
+        (ColferType::Uint8, false) | (ColferType::Uint16, true) => 1,
+        (ColferType::Uint16, false) => 2,
+        (ColferType::Uint32, true) | (ColferType::Float32, false) => 4,
+        (ColferType::Uint64, true) | (ColferType::Float64, false) => 8,
+        (ColferType::Timestamp, false) => 8,
+        (ColferType::Timestamp, true) => 12,
+        (ColferType::Uint32, false) | (ColferType::Int32, _) => {
+            get_varint32(buf, false)?;
+            return Ok(());
+        }
+        (ColferType::Uint64, false) | (ColferType::Int64, _) => {
+            get_varint64(buf, false)?;
+            return Ok(());
+        }
+        (ColferType::Text, false) | (ColferType::Binary, false) => {
+            get_binary(buf, field.name, false)?;
+            return Ok(());
+        }
+        (ColferType::Struct(desc), false) => {
+            let data: &[u8] = buf.get_ref();
+            let start = buf.position();
+            let n = scan_message(desc, &data[start..], |_, _| {})?;
+            buf.set_position(start + n);
+            return Ok(());
+        }
+        _ => {
+            return Err(ColferError::UnknownHeader {
+                byte: buf.position() - 1,
+            })
+        }
+    };
+    buf_guard!(buf, width);
+    let pos = buf.position();
+    buf.set_position(pos + width);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cursor past the header byte at the start of data, as the decoders expect.
+    fn payload<'a>(data: &'a [u8]) -> Cursor<'a> {
+        let mut buf = Cursor::new(data);
+        buf.set_position(1);
+        buf
+    }
+
+    #[test]
+    fn varint32_minimal() {
+        let mut buf = Cursor::new(&[0x80, 0x01]);
+        assert_eq!(get_varint32(&mut buf, true).unwrap(), 128);
+        assert_eq!(buf.position(), 2);
+
+        let mut buf = Cursor::new(&[0x85, 0x00]);
+        assert!(matches!(get_varint32(&mut buf, true), Err(ColferError::NonMinimalVarint { byte: 0 })));
+        let mut buf = Cursor::new(&[0x85, 0x00]);
+        assert_eq!(get_varint32(&mut buf, false).unwrap(), 5);
+    }
+
+    #[test]
+    fn varint64_minimal() {
+        let mut buf = Cursor::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(get_varint64(&mut buf, true).unwrap(), 0xFFFF_FFFF);
+
+        let mut buf = Cursor::new(&[0x80, 0x80, 0x00]);
+        assert!(matches!(get_varint64(&mut buf, true), Err(ColferError::NonMinimalVarint { byte: 0 })));
+        let mut buf = Cursor::new(&[0x80, 0x80, 0x00]);
+        assert_eq!(get_varint64(&mut buf, false).unwrap(), 0);
+    }
+
+    #[test]
+    fn timestamp_canonical() {
+        let data = [0x07, 0, 0, 0, 1, 0, 0, 0, 2];
+        assert_eq!(get_timestamp(&mut payload(&data), false, true).unwrap(), Timestamp::new(1, 2));
+
+        let data = [0x87, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+        assert_eq!(get_timestamp(&mut payload(&data), true, true).unwrap(), Timestamp::new(-1, 0));
+    }
+
+    #[test]
+    fn timestamp_zero() {
+        let data = [0x07, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(matches!(get_timestamp(&mut payload(&data), false, true), Err(ColferError::ZeroValue { byte: 0 })));
+        assert_eq!(get_timestamp(&mut payload(&data), false, false).unwrap(), Timestamp::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn timestamp_wide_in_u32_range() {
+        let data = [0x87, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+        assert!(matches!(get_timestamp(&mut payload(&data), true, true), Err(ColferError::NonCanonicalForm { byte: 0 })));
+        assert_eq!(get_timestamp(&mut payload(&data), true, false).unwrap(), Timestamp::new(0xFFFF_FFFF, 0));
+    }
+
+    #[test]
+    fn timestamp_nanos_overflow() {
+        // 1e9 nanoseconds, which belongs in the seconds.
+        let data = [0x07, 0, 0, 0, 0, 0x3B, 0x9A, 0xCA, 0x00];
+        assert!(matches!(get_timestamp(&mut payload(&data), false, true), Err(ColferError::NonCanonicalForm { byte: 0 })));
+        assert!(get_timestamp(&mut payload(&data), false, false).is_ok());
+
+        let data = [0x07, 0, 0, 0, 0, 0x3B, 0x9A, 0xC9, 0xFF];
+        assert_eq!(get_timestamp(&mut payload(&data), false, true).unwrap(), Timestamp::new(0, 999_999_999));
+    }
+}
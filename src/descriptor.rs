@@ -0,0 +1,99 @@
+use core::fmt;
+use core::ptr;
+
+/// Colfer data type of a field.
+#[derive(Clone, Copy)]
+pub enum ColferType {
+    Bool,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    Timestamp,
+    Text,
+    Binary,
+    /// Nested data structure of the given type.
+    Struct(&'static MessageDescriptor),
+}
+
+impl ColferType {
+    /// Name of the type in Colfer schemas.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColferType::Bool => "bool",
+            ColferType::Uint8 => "uint8",
+            ColferType::Uint16 => "uint16",
+            ColferType::Uint32 => "uint32",
+            ColferType::Uint64 => "uint64",
+            ColferType::Int32 => "int32",
+            ColferType::Int64 => "int64",
+            ColferType::Float32 => "float32",
+            ColferType::Float64 => "float64",
+            ColferType::Timestamp => "timestamp",
+            ColferType::Text => "text",
+            ColferType::Binary => "binary",
+            ColferType::Struct(desc) => desc.name,
+        }
+    }
+}
+
+// Struct types may refer to themselves, so they print by name only and compare by
+// descriptor identity, as data structures of different packages may share a name.
+impl PartialEq for ColferType {
+    fn eq(&self, other: &ColferType) -> bool {
+        match (*self, *other) {
+            (ColferType::Struct(a), ColferType::Struct(b)) => ptr::eq(a, b),
+            (a, b) => a.name() == b.name(),
+        }
+    }
+}
+
+impl fmt::Debug for ColferType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColferType::Struct(desc) => write!(f, "Struct({})", desc.name),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+/// Describes one field of a Colfer data structure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldDescriptor {
+    /// Field name as in the schema, e.g. `U32`.
+    pub name: &'static str,
+    /// Position of the field in the schema, which is also its header on the wire.
+    pub index: u8,
+    pub ty: ColferType,
+    /// Whether the field holds a list of `ty`.
+    pub list: bool,
+    /// Doc comment from the schema, without the leading slashes.
+    pub doc: &'static str,
+}
+
+/// Describes a Colfer data structure, as exposed by `ColferSerializable::DESCRIPTOR`.
+#[derive(Debug)]
+pub struct MessageDescriptor {
+    /// Type name, e.g. `ColferTypes`.
+    pub name: &'static str,
+    /// Doc comment from the schema, without the leading slashes.
+    pub doc: &'static str,
+    /// Fields in ascending index order.
+    pub fields: &'static [FieldDescriptor],
+}
+
+impl MessageDescriptor {
+    /// Looks up a field by its index.
+    pub fn field(&self, index: u8) -> Option<&'static FieldDescriptor> {
+        self.fields.iter().find(|f| f.index == index)
+    }
+
+    /// Looks up a field by its name.
+    pub fn field_by_name(&self, name: &str) -> Option<&'static FieldDescriptor> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
@@ -1,23 +1,70 @@
-#[derive(Debug, Fail)]
+use core::fmt;
+
+#[derive(Debug)]
 pub enum ColferError {
-    #[fail(display = "colfer: field {} exceeds {} bytes", field, overflow)]
-    MaxSizeBreach {
-        field: &'static str,
-        overflow: usize,
-    },
-    #[fail(display = "colfer: field {} exceeds {} elements", field, overflow)]
-    MaxListBreach {
-        field: &'static str,
-        overflow: usize,
-    },
-    #[fail(display = "colfer: unknown header at byte {}", byte)]
+    MaxSizeBreach { field: &'static str, overflow: usize },
+    MaxListBreach { field: &'static str, overflow: usize },
     UnknownHeader { byte: usize },
-    #[fail(display = "colfer: data continuation at byte {}", byte)]
+    HeaderOrder { byte: usize },
+    DuplicateField { byte: usize },
+    ZeroValue { byte: usize },
+    NonMinimalVarint { byte: usize },
+    NonCanonicalForm { byte: usize },
+    FieldMismatch { index: u8 },
+    ReencodeRequired { index: u8 },
+    BufferTooSmall { overflow: usize },
+    TimestampRange,
     Tail { byte: usize },
-    #[fail(display = "colfer: unexpected empty buffer")]
+    InvalidUtf8 { byte: usize },
+    InvalidValue { byte: usize },
     UnexpectedEof,
-    #[fail(display = "colfer: unknown error")]
     Unknown,
 }
 
+impl ColferError {
+    /// Moves the byte offset of the error, if any, by `start`. Nested messages decode from
+    /// their own start, so their errors are moved to be relative to the outer message.
+    pub fn offset(self, start: usize) -> Self {
+        match self {
+            ColferError::UnknownHeader { byte } => ColferError::UnknownHeader { byte: byte + start },
+            ColferError::HeaderOrder { byte } => ColferError::HeaderOrder { byte: byte + start },
+            ColferError::DuplicateField { byte } => ColferError::DuplicateField { byte: byte + start },
+            ColferError::ZeroValue { byte } => ColferError::ZeroValue { byte: byte + start },
+            ColferError::NonMinimalVarint { byte } => ColferError::NonMinimalVarint { byte: byte + start },
+            ColferError::NonCanonicalForm { byte } => ColferError::NonCanonicalForm { byte: byte + start },
+            ColferError::Tail { byte } => ColferError::Tail { byte: byte + start },
+            ColferError::InvalidUtf8 { byte } => ColferError::InvalidUtf8 { byte: byte + start },
+            ColferError::InvalidValue { byte } => ColferError::InvalidValue { byte: byte + start },
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for ColferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColferError::MaxSizeBreach { field, overflow } => write!(f, "colfer: field {} exceeds {} bytes", field, overflow),
+            ColferError::MaxListBreach { field, overflow } => write!(f, "colfer: field {} exceeds {} elements", field, overflow),
+            ColferError::UnknownHeader { byte } => write!(f, "colfer: unknown header at byte {}", byte),
+            ColferError::HeaderOrder { byte } => write!(f, "colfer: header out of order at byte {}", byte),
+            ColferError::DuplicateField { byte } => write!(f, "colfer: duplicate field at byte {}", byte),
+            ColferError::ZeroValue { byte } => write!(f, "colfer: explicit zero value at byte {}", byte),
+            ColferError::NonMinimalVarint { byte } => write!(f, "colfer: non-minimal varint at byte {}", byte),
+            ColferError::NonCanonicalForm { byte } => write!(f, "colfer: non-canonical encoding form at byte {}", byte),
+            ColferError::FieldMismatch { index } => write!(f, "colfer: value doesn't match the type of field {}", index),
+            ColferError::ReencodeRequired { index } => write!(f, "colfer: field {} can't be patched in place, re-encode required", index),
+            ColferError::BufferTooSmall { overflow } => write!(f, "colfer: output buffer short by {} bytes", overflow),
+            ColferError::TimestampRange => write!(f, "colfer: timestamp out of range"),
+            ColferError::Tail { byte } => write!(f, "colfer: data continuation at byte {}", byte),
+            ColferError::InvalidUtf8 { byte } => write!(f, "colfer: invalid UTF-8 text at byte {}", byte),
+            ColferError::InvalidValue { byte } => write!(f, "colfer: value of field at byte {} rejected by its type", byte),
+            ColferError::UnexpectedEof => write!(f, "colfer: unexpected empty buffer"),
+            ColferError::Unknown => write!(f, "colfer: unknown error"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for ColferError {}
+
 pub type ColferResult<T> = Result<T, ColferError>;
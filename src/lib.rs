@@ -1,20 +1,121 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "arena")]
+extern crate bumpalo;
 extern crate bytes;
-#[macro_use]
-extern crate failure;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "heapless")]
+extern crate heapless;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "time")]
+extern crate time;
 
+#[macro_use]
+mod macros;
+pub mod bulk;
+#[cfg(feature = "schema")]
+pub mod codegen;
+#[cfg(feature = "schema")]
+pub mod compat;
+mod cursor;
+mod decode;
+mod descriptor;
 mod error;
+#[cfg(feature = "schema")]
+pub mod lint;
+mod options;
+mod patch;
+#[cfg(feature = "alloc")]
+mod pool;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+mod store;
+mod timestamp;
 mod types;
+pub mod varint;
+mod view;
+mod visitor;
+
+pub use self::descriptor::{ColferType, FieldDescriptor, MessageDescriptor};
+pub use self::error::{ColferError, ColferResult};
+pub use self::options::{DecodeOptions, FieldSet, UnknownFields};
+pub use self::patch::{patch_field, patch_message, PatchValue};
+#[cfg(feature = "alloc")]
+pub use self::pool::MessagePool;
+pub use self::timestamp::Timestamp;
+pub use self::view::ColferView;
+pub use self::visitor::{visit, visit_message, ColferVisitor};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use bytes::BufMut;
 
-use self::error::{ColferError, ColferResult};
+/// What the code of `codegen` builds on. Not part of the public API, as it changes along
+/// with the generator.
+#[doc(hidden)]
+pub mod rt {
+    #[cfg(feature = "alloc")]
+    pub use alloc::boxed::Box;
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+    pub use bytes::{Buf, BufMut};
+    pub use core::convert::TryFrom;
+    pub use core::mem;
+
+    pub use super::cursor::Cursor;
+    pub use super::decode::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8,
+                            get_varint32, get_varint64, skip_field};
+}
 
 pub const COLFER_SIZE_MAX: usize = 16 * 1024 * 1024;
 pub const COLFER_LIST_MAX: usize = 64 * 1024;
 
 pub trait ColferSerializable<'a> {
-    fn colf_marshal_to(&self, buf: &mut Vec<u8>) -> usize;
+    /// Describes the fields of the type.
+    const DESCRIPTOR: &'static MessageDescriptor;
+
+    /// Appends the encoding to `buf` and returns the number of bytes written. The size
+    /// limits are not checked, as `colf_marshal_len` does that.
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize;
+    /// Returns the encoded size, or an error when the message breaches a size limit. Nested
+    /// messages are not length-prefixed in Colfer, so this is a single pass over the tree and
+    /// `colf_marshal_to` never needs it: encoding stays linear in the size of the tree.
     fn colf_marshal_len(&self) -> ColferResult<usize>;
-    fn colf_unmarshal(&mut self, data: &'a [u8]) -> ColferResult<usize>;
+    /// Sets all fields to their zero value. Lists and binaries keep their capacity.
+    fn colf_reset(&mut self);
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize>;
+
+    fn colf_unmarshal(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_unmarshal_with(data, &DecodeOptions::default())
+    }
 
+    /// Decodes into a reset message, so that fields absent from `data` end up zero instead
+    /// of keeping their current value. Generated types refill lists in place and decode
+    /// into the nested messages they already have, which makes decoding into a reused
+    /// message mostly allocation free. The default just resets first.
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_reset();
+        self.colf_unmarshal(data)
+    }
+
+    /// Decodes only the fields with the given indices, skipping the payloads of all others.
+    fn colf_unmarshal_fields(&mut self, data: &'a [u8], fields: &[u8]) -> ColferResult<usize> {
+        let opts = DecodeOptions {
+            fields: FieldSet::from_indices(fields),
+            ..DecodeOptions::default()
+        };
+        self.colf_unmarshal_with(data, &opts)
+    }
+
+    #[cfg(feature = "alloc")]
     fn colf_marshal_binary(&self) -> ColferResult<Vec<u8>> {
         let l = self.colf_marshal_len()?;
         let mut data = Vec::with_capacity(l);
@@ -22,8 +123,24 @@ pub trait ColferSerializable<'a> {
         Ok(data)
     }
 
+    /// Encodes into the start of `out` and returns the number of bytes written, without
+    /// allocating. Fails with `ColferError::BufferTooSmall` when the encoding doesn't fit,
+    /// in which case `out` is left as is.
+    fn colf_marshal_into(&self, out: &mut [u8]) -> ColferResult<usize> {
+        let l = self.colf_marshal_len()?;
+        if l > out.len() {
+            return Err(ColferError::BufferTooSmall { overflow: l - out.len() });
+        }
+        Ok(self.colf_marshal_to(&mut &mut out[..]))
+    }
+
     fn colf_unmarshal_binary(&mut self, data: &'a [u8]) -> ColferResult<usize> {
-        let byte = self.colf_unmarshal(data)?;
+        self.colf_unmarshal_binary_with(data, &DecodeOptions::default())
+    }
+
+    fn colf_unmarshal_binary_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        let opts = DecodeOptions { whole_input: true, ..*opts };
+        let byte = self.colf_unmarshal_with(data, &opts)?;
         if byte >= data.len() {
             Ok(byte)
         } else {
@@ -32,4 +149,12 @@ pub trait ColferSerializable<'a> {
     }
 }
 
+#[cfg(feature = "alloc")]
 pub use self::types::ColferTypes;
+pub use self::types::ColferTypesView;
+#[cfg(feature = "arena")]
+pub use self::types::ColferTypesArena;
+#[cfg(feature = "heapless")]
+pub use self::types::ColferTypesHeapless;
+#[cfg(feature = "arena")]
+pub use bumpalo::Bump;
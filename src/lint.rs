@@ -0,0 +1,188 @@
+//! Checks a parsed schema file for what the parser lets through but causes trouble later:
+//! names that upstream generators or the Rust one can't use as is, more fields than
+//! Colfer has headers for, and missing documentation.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::codegen::{ident, is_rust_keyword, member_name, type_name};
+use super::schema::{Package, Struct};
+
+/// Problem found by `lint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Line of the declaration with the problem.
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+/// Lists the problems of a parsed file in line order.
+pub fn lint(pkg: &Package) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut push = |line, msg| lints.push(Lint { line, msg });
+
+    if pkg.doc.is_empty() {
+        push(pkg.line, format!("package {} has no doc comment", pkg.name));
+    }
+    if is_rust_keyword(&pkg.name) {
+        push(pkg.line, format!("package {} is a Rust keyword, so its module is named {}", pkg.name, ident(&pkg.name)));
+    }
+
+    for (i, t) in pkg.types.iter().enumerate() {
+        if pkg.types[..i].iter().any(|o| o.name == t.name) {
+            push(t.line, format!("type {} declared twice", t.name));
+        }
+        if t.doc.is_empty() {
+            push(t.line, format!("type {} has no doc comment", t.name));
+        }
+        if !exported(&t.name) {
+            push(t.line, format!("type {} can't be exported in Go, as it doesn't start with a letter", t.name));
+        }
+        let rust = type_name(&t.name);
+        if rust != upper_first(&t.name) {
+            push(t.line, format!("type {} is a Rust keyword once capitalized, so it is generated as {}", t.name, rust));
+        }
+        if t.fields.len() > 127 {
+            push(t.line, format!("type {} has {} fields, more than the 127 Colfer has headers for", t.name, t.fields.len()));
+        }
+        fields(t, &mut push);
+    }
+
+    lints.sort_by_key(|l| l.line);
+    lints
+}
+
+fn fields<F: FnMut(usize, String)>(t: &Struct, push: &mut F) {
+    for (i, f) in t.fields.iter().enumerate() {
+        if t.fields[..i].iter().any(|o| o.name == f.name) {
+            push(f.line, format!("field {}.{} declared twice", t.name, f.name));
+        }
+        if f.doc_text().is_empty() {
+            push(f.line, format!("field {}.{} has no doc comment", t.name, f.name));
+        }
+        if !exported(&f.name) {
+            push(f.line, format!("field {}.{} can't be exported in Go, as it doesn't start with a letter", t.name, f.name));
+        }
+        if is_rust_keyword(&f.name) {
+            push(f.line, format!("field {}.{} is a Rust keyword, so it is generated as {}", t.name, f.name, ident(&f.name)));
+        } else if member_name(&f.name) != f.name {
+            push(f.line, format!("field {}.{} is taken by the generated code, so it is generated as {}", t.name, f.name, member_name(&f.name)));
+        }
+        if let Some(o) = t.fields[..i].iter().find(|o| o.name != f.name && member_name(&o.name) == member_name(&f.name)) {
+            push(f.line, format!("field {}.{} is generated as {}, like field {}", t.name, f.name, member_name(&f.name), o.name));
+        }
+    }
+}
+
+// Upstream generators export names by capitalizing their first letter, which needs one.
+fn exported(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+fn upper_first(name: &str) -> String {
+    let mut s = String::from(name);
+    if let Some(c) = s.get_mut(..1) {
+        c.make_ascii_uppercase();
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::schema::parse;
+
+    // Messages of the lints on package p, declared documented, holding the given types.
+    fn lints(types: &str) -> Vec<String> {
+        lint(&parse(&format!("// Package p is linted.\npackage p\n{}", types)).unwrap()).into_iter().map(|l| l.msg).collect()
+    }
+
+    #[test]
+    fn clean() {
+        assert!(lints("\n// T is a type.\ntype t struct {\n\t// A is a field.\n\ta text\n}\n").is_empty());
+    }
+
+    #[test]
+    fn missing_docs() {
+        let got = lint(&parse("package p\n\ntype t struct {\n\ta text // colfer:size_max=8\n}\n").unwrap());
+        assert_eq!(
+            got,
+            [
+                Lint {
+                    line: 1,
+                    msg: String::from("package p has no doc comment"),
+                },
+                Lint {
+                    line: 3,
+                    msg: String::from("type t has no doc comment"),
+                },
+                Lint {
+                    line: 4,
+                    msg: String::from("field t.a has no doc comment"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rust_keywords() {
+        let got = lint(&parse("// Package match is a keyword.\npackage match\n").unwrap());
+        assert_eq!(got[0].msg, "package match is a Rust keyword, so its module is named match_");
+
+        let got = lints("// Self is a keyword once capitalized.\ntype self struct {\n\t// Type is a keyword.\n\ttype text\n}\n");
+        assert_eq!(
+            got,
+            [
+                "type self is a Rust keyword once capitalized, so it is generated as Self_",
+                "field self.type is a Rust keyword, so it is generated as type_",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_leading_letter() {
+        let got = lints("// T starts with an underscore.\ntype _t struct {\n\t// A starts with an underscore.\n\t_a text\n}\n");
+        assert_eq!(
+            got,
+            [
+                "type _t can't be exported in Go, as it doesn't start with a letter",
+                "field _t._a can't be exported in Go, as it doesn't start with a letter",
+            ]
+        );
+    }
+
+    #[test]
+    fn declared_twice() {
+        let got = lints("// T is a type.\ntype t struct {\n\t// A is a field.\n\ta text\n\t// A again.\n\ta bool\n}\n// T again.\ntype t struct {\n}\n");
+        assert_eq!(got, ["field t.a declared twice", "type t declared twice"]);
+    }
+
+    #[test]
+    fn member_name_taken() {
+        let got = lints("// T is a type.\ntype t struct {\n\t// New is taken.\n\tnew text\n\t// New_ collides.\n\tnew_ bool\n}\n");
+        assert_eq!(
+            got,
+            [
+                "field t.new is taken by the generated code, so it is generated as new_",
+                "field t.new_ is generated as new_, like field new",
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_fields() {
+        let fields: String = (0..128).map(|i| format!("\t// F{0} is a field.\n\tf{0} bool\n", i)).collect();
+        let got = lints(&format!("// T is a type.\ntype t struct {{\n{}}}\n", fields));
+        assert_eq!(got, ["type t has 128 fields, more than the 127 Colfer has headers for"]);
+        let fields: String = (0..127).map(|i| format!("\t// F{0} is a field.\n\tf{0} bool\n", i)).collect();
+        assert!(lints(&format!("// T is a type.\ntype t struct {{\n{}}}\n", fields)).is_empty());
+    }
+}
@@ -0,0 +1,20 @@
+macro_rules! buf_guard {
+    ($buf:ident) => {
+        if !$buf.has_remaining() {
+            return Err(ColferError::UnexpectedEof);
+        }
+    };
+    ($buf:ident, $n:expr) => {
+        if $buf.remaining() < $n {
+            return Err(ColferError::UnexpectedEof);
+        }
+    };
+}
+
+macro_rules! canonical_guard {
+    ($canonical:ident, $cond:expr, $err:ident, $byte:expr) => {
+        if $canonical && !($cond) {
+            return Err(ColferError::$err { byte: $byte });
+        }
+    };
+}
@@ -0,0 +1,86 @@
+/// How the decoder treats headers it has no field for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownFields {
+    /// Fails with `ColferError::UnknownHeader` on the first unknown field.
+    #[default]
+    Strict,
+    /// Keeps the unknown fields of a top-level message in its `unknown_fields` buffer,
+    /// which is emitted again on marshal. Colfer headers don't carry a payload length, so
+    /// the end of unknown fields is only known when the data holds exactly one message,
+    /// as with `colf_unmarshal_binary_with`. Otherwise, and inside nested messages,
+    /// unknown fields still fail with `ColferError::UnknownHeader`.
+    Preserve,
+}
+
+/// Set of field indices. Colfer allows up to 127 fields, so it fits one bit per field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSet(u128);
+
+impl FieldSet {
+    /// Contains every field.
+    pub fn all() -> Self {
+        FieldSet(!0)
+    }
+
+    /// Contains no field.
+    pub fn empty() -> Self {
+        FieldSet(0)
+    }
+
+    /// Contains the given field indices. Indices past 127 are ignored.
+    pub fn from_indices(indices: &[u8]) -> Self {
+        let mut set = FieldSet::empty();
+        for &index in indices {
+            set.insert(index);
+        }
+        set
+    }
+
+    pub fn insert(&mut self, index: u8) {
+        if index < 128 {
+            self.0 |= 1 << index;
+        }
+    }
+
+    pub fn contains(&self, index: u8) -> bool {
+        index < 128 && self.0 & (1 << index) != 0
+    }
+}
+
+impl Default for FieldSet {
+    fn default() -> Self {
+        FieldSet::all()
+    }
+}
+
+/// Options for `ColferSerializable::colf_unmarshal_with`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DecodeOptions {
+    pub unknown_fields: UnknownFields,
+    /// Rejects any encoding other than the canonical one: fields in ascending order,
+    /// zero values omitted, minimal varints and the fixed-width forms only above their
+    /// thresholds. Two messages with equal fields then always have equal bytes.
+    pub validate_canonical: bool,
+    /// Fields of the top-level message to decode. The payloads of the others are skipped
+    /// without being checked, nested data structures included, and their values are left
+    /// untouched.
+    pub fields: FieldSet,
+    /// Whether the data holds exactly one message, which `colf_unmarshal_binary_with`
+    /// sets. Unknown fields can then be preserved up to the terminator in the last byte.
+    #[doc(hidden)]
+    pub whole_input: bool,
+}
+
+impl DecodeOptions {
+    /// Options to decode a nested message with: its end is only known once all of its
+    /// fields are read, so unknown fields can't be preserved there. Nested messages are
+    /// decoded with all of their fields.
+    #[doc(hidden)]
+    pub fn nested(&self) -> Self {
+        let mut opts = *self;
+        opts.unknown_fields = UnknownFields::Strict;
+        opts.fields = FieldSet::all();
+        opts.whole_input = false;
+        opts
+    }
+}
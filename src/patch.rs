@@ -0,0 +1,279 @@
+use super::{cursor::Cursor,
+            decode::{scan_message, skip_field},
+            descriptor::{ColferType, MessageDescriptor},
+            error::{ColferError, ColferResult},
+            timestamp::Timestamp,
+            varint,
+            ColferSerializable};
+
+/// New value for `patch_field`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchValue {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Timestamp(Timestamp),
+}
+
+/// Overwrites the value of a field in the message of type `T` encoded at the start of
+/// `data`, without re-encoding the message. This only works when the new value encodes
+/// to the same header and the same number of bytes as the current one, e.g. a `uint64`
+/// that stays in its fixed-width form or any non-zero `float64`, so the message keeps
+/// its canonical encoding. Otherwise `data` is left as is and the call fails with
+/// `ColferError::ReencodeRequired`, as it does for a field that is absent or becomes zero.
+pub fn patch_field<'a, T>(data: &mut [u8], index: u8, value: PatchValue) -> ColferResult<()>
+where
+    T: ColferSerializable<'a>,
+{
+    patch_message(T::DESCRIPTOR, data, index, value)
+}
+
+/// Same as `patch_field`, for the type described by `desc`.
+pub fn patch_message(desc: &'static MessageDescriptor, data: &mut [u8], index: u8, value: PatchValue) -> ColferResult<()> {
+    let field = match desc.field(index) {
+        Some(field) if !field.list => field,
+        _ => return Err(ColferError::FieldMismatch { index }),
+    };
+
+    let mut header = None;
+    scan_message(desc, data, |f, byte| {
+        if f.index == index {
+            header = Some(byte);
+        }
+    })?;
+
+    let mut new = [0; 13];
+    let n = encode(field.ty, value, index, &mut new)?;
+    let byte = match header {
+        Some(byte) => byte,
+        None if n == 0 => return Ok(()),
+        None => return Err(ColferError::ReencodeRequired { index }),
+    };
+
+    let end = {
+        let mut buf = Cursor::new(&data[..]);
+        buf.set_position(byte + 1);
+        skip_field(field, data[byte] & 0x80 != 0, &mut buf)?;
+        buf.position()
+    };
+    if n != end - byte || new[0] != data[byte] {
+        return Err(ColferError::ReencodeRequired { index });
+    }
+    data[byte..end].copy_from_slice(&new[..n]);
+    Ok(())
+}
+
+// Writes the canonical encoding of the value, header included, and returns its length,
+// which is zero for zero values as these are omitted.
+fn encode(ty: ColferType, value: PatchValue, index: u8, out: &mut [u8; 13]) -> ColferResult<usize> {
+    let mut n = 1;
+    let flag = match (ty, value) {
+        (ColferType::Bool, PatchValue::Bool(v)) => {
+            if !v {
+                return Ok(0);
+            }
+            false
+        }
+        (ColferType::Uint8, PatchValue::U8(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            out[1] = v;
+            n = 2;
+            false
+        }
+        (ColferType::Uint16, PatchValue::U16(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            if v >= 1 << 8 {
+                out[1..3].copy_from_slice(&v.to_be_bytes());
+                n = 3;
+                false
+            } else {
+                out[1] = v as u8;
+                n = 2;
+                true
+            }
+        }
+        (ColferType::Uint32, PatchValue::U32(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            if v >= 1 << 21 {
+                out[1..5].copy_from_slice(&v.to_be_bytes());
+                n = 5;
+                true
+            } else {
+                n += varint::encode_u32(&mut out[1..], v);
+                false
+            }
+        }
+        (ColferType::Uint64, PatchValue::U64(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            if v >= 1 << 49 {
+                out[1..9].copy_from_slice(&v.to_be_bytes());
+                n = 9;
+                true
+            } else {
+                n += varint::encode_u64(&mut out[1..], v);
+                false
+            }
+        }
+        (ColferType::Int32, PatchValue::I32(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            n += varint::encode_u32(&mut out[1..], v.unsigned_abs());
+            v < 0
+        }
+        (ColferType::Int64, PatchValue::I64(v)) => {
+            if v == 0 {
+                return Ok(0);
+            }
+            n += varint::encode_u64(&mut out[1..], v.unsigned_abs());
+            v < 0
+        }
+        (ColferType::Float32, PatchValue::F32(v)) => {
+            if v == 0.0 {
+                return Ok(0);
+            }
+            out[1..5].copy_from_slice(&v.to_bits().to_be_bytes());
+            n = 5;
+            false
+        }
+        (ColferType::Float64, PatchValue::F64(v)) => {
+            if v == 0.0 {
+                return Ok(0);
+            }
+            out[1..9].copy_from_slice(&v.to_bits().to_be_bytes());
+            n = 9;
+            false
+        }
+        (ColferType::Timestamp, PatchValue::Timestamp(v)) => {
+            let (s, ns) = (v.seconds, v.nanos);
+            if v.is_zero() {
+                return Ok(0);
+            }
+            if (0..1 << 32).contains(&s) {
+                out[1..5].copy_from_slice(&(s as u32).to_be_bytes());
+                out[5..9].copy_from_slice(&ns.to_be_bytes());
+                n = 9;
+                false
+            } else {
+                out[1..9].copy_from_slice(&s.to_be_bytes());
+                out[9..13].copy_from_slice(&ns.to_be_bytes());
+                n = 13;
+                true
+            }
+        }
+        _ => return Err(ColferError::FieldMismatch { index }),
+    };
+
+    out[0] = if flag { index | 0x80 } else { index };
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    use super::super::types::ColferTypes;
+
+    fn encode_message(o: &ColferTypes) -> Vec<u8> {
+        o.colf_marshal_binary().unwrap()
+    }
+
+    fn decode_message<'a>(data: &'a [u8]) -> ColferTypes<'a> {
+        let mut o = ColferTypes::default();
+        o.colf_unmarshal_binary(data).unwrap();
+        o
+    }
+
+    #[test]
+    fn fixed_width() {
+        let mut data = encode_message(&ColferTypes {
+            U64: 1 << 50,
+            F64: 1.5,
+            S: "tail",
+            ..ColferTypes::default()
+        });
+        patch_field::<ColferTypes>(&mut data, 6, PatchValue::F64(-2.25)).unwrap();
+        patch_field::<ColferTypes>(&mut data, 2, PatchValue::U64(u64::MAX)).unwrap();
+
+        let o = decode_message(&data);
+        assert_eq!(o.F64, -2.25);
+        assert_eq!(o.U64, u64::MAX);
+        assert_eq!(o.S, "tail");
+    }
+
+    #[test]
+    fn varint_same_size() {
+        let mut data = encode_message(&ColferTypes {
+            U32: 5,
+            I64: -300,
+            ..ColferTypes::default()
+        });
+        patch_field::<ColferTypes>(&mut data, 1, PatchValue::U32(127)).unwrap();
+        patch_field::<ColferTypes>(&mut data, 4, PatchValue::I64(-16000)).unwrap();
+
+        let o = decode_message(&data);
+        assert_eq!(o.U32, 127);
+        assert_eq!(o.I64, -16000);
+    }
+
+    // The patch must fail without touching the data.
+    fn reencode(o: &ColferTypes, index: u8, value: PatchValue) {
+        let mut data = encode_message(o);
+        let before = data.clone();
+        match patch_field::<ColferTypes>(&mut data, index, value) {
+            Err(ColferError::ReencodeRequired { index: i }) => assert_eq!(i, index),
+            r => panic!("patch of field {} to {:?}: got {:?}", index, value, r),
+        }
+        assert_eq!(data, before);
+    }
+
+    #[test]
+    fn size_change() {
+        let o = ColferTypes {
+            U32: 5,
+            U64: 1 << 50,
+            T: Timestamp::new(1, 0),
+            ..ColferTypes::default()
+        };
+        // Varint grows.
+        reencode(&o, 1, PatchValue::U32(300));
+        // Fixed-width form becomes a varint.
+        reencode(&o, 2, PatchValue::U64(7));
+        // Timestamp needs the wide form.
+        reencode(&o, 7, PatchValue::Timestamp(Timestamp::new(-1, 0)));
+    }
+
+    #[test]
+    fn header_flag_change() {
+        let o = ColferTypes {
+            I32: 5,
+            U16: 300,
+            ..ColferTypes::default()
+        };
+        // Same size, negative flag.
+        reencode(&o, 3, PatchValue::I32(-5));
+        // Two-byte form becomes the flagged one-byte form.
+        reencode(&o, 15, PatchValue::U16(255));
+    }
+
+    #[test]
+    fn presence_change() {
+        reencode(&ColferTypes { F32: 1.0, ..ColferTypes::default() }, 5, PatchValue::F32(0.0));
+        reencode(&ColferTypes::default(), 5, PatchValue::F32(1.0));
+    }
+}
@@ -0,0 +1,90 @@
+use alloc::vec::Vec;
+
+use super::ColferSerializable;
+
+/// Free list of messages to decode into, so that hot decode loops reuse the allocations
+/// of earlier messages instead of going to the allocator. As decoded messages borrow
+/// from their input, the buffers decoded from must outlive the pool.
+#[derive(Debug)]
+pub struct MessagePool<T> {
+    free: Vec<T>,
+    max: usize,
+}
+
+impl<T> MessagePool<T> {
+    /// Creates a pool that holds on to at most `max` idle messages.
+    pub fn new(max: usize) -> Self {
+        MessagePool { free: Vec::new(), max }
+    }
+
+    /// Returns the number of idle messages.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// Takes an idle message, or a new one when there is none. Messages are reset when
+    /// returned with `put`, so all fields are zero either way.
+    pub fn get(&mut self) -> T
+    where
+        T: Default,
+    {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// Resets the message and keeps it for a later `get`, or drops it when the pool is full.
+    pub fn put<'a>(&mut self, mut msg: T)
+    where
+        T: ColferSerializable<'a>,
+    {
+        if self.free.len() < self.max {
+            msg.colf_reset();
+            self.free.push(msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use types::ColferTypes;
+
+    #[test]
+    fn get_put() {
+        let mut pool = MessagePool::<ColferTypes>::new(2);
+        assert!(pool.is_empty());
+        let mut o = pool.get();
+        assert_eq!((o.U32, o.F64s.len()), (0, 0));
+
+        o.U32 = 5;
+        o.S = "text";
+        o.F64s = vec![0.5; 10];
+        let ptr = o.F64s.as_ptr();
+        pool.put(o);
+        assert_eq!(pool.len(), 1);
+
+        // The message comes back reset, with its list allocation.
+        let o = pool.get();
+        assert!(pool.is_empty());
+        assert_eq!((o.U32, o.S, o.F64s.len()), (0, "", 0));
+        assert!(o.F64s.capacity() >= 10);
+        assert_eq!(o.F64s.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn holds_at_most_max() {
+        let mut pool = MessagePool::<ColferTypes>::new(2);
+        for _ in 0..3 {
+            pool.put(ColferTypes::default());
+        }
+        assert_eq!(pool.len(), 2);
+
+        let mut pool = MessagePool::<ColferTypes>::new(0);
+        pool.put(ColferTypes::default());
+        assert!(pool.is_empty());
+    }
+}
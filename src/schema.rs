@@ -0,0 +1,788 @@
+//! Colfer schemas, as read from `.colf` files in the Go-like syntax of upstream Colfer:
+//!
+//! ```text
+//! // Package demo offers a demonstration.
+//! package demo
+//!
+//! // Course is the grounds where the game of golf is played.
+//! type course struct {
+//!     ID    uint64
+//!     name  text
+//!     holes []hole
+//!     owner people.member
+//! }
+//! ```
+//!
+//! Fields are numbered in order of appearance, which makes their index on the wire. Data
+//! structures of other packages are referred to as `package.type`; a `Schema` holds the
+//! packages that are compiled together and resolves these references.
+//!
+//! Fields take annotations as words in their comments. `colfer:size_max=N` limits a text
+//! or binary, or each one of a list, to N bytes, and `colfer:list_max=N` limits a list to
+//! N elements, in place of `COLFER_SIZE_MAX` and `COLFER_LIST_MAX`:
+//!
+//! ```text
+//! type member struct {
+//!     name  text   // colfer:size_max=64
+//!     roles []text // colfer:list_max=8 colfer:size_max=32
+//!     // colfer:enum=Level colfer:values=guest,member,admin=9
+//!     level uint8
+//! }
+//! ```
+//!
+//! A uint8 or uint16 with `colfer:enum=Name` gets an enum of the values named with
+//! `colfer:values`, numbered from 0 on unless given with `=N`. Values without a name fail
+//! to decode, unless `colfer:unknown=keep` adds an `Unknown` variant to hold them.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+
+use super::{COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Error in a schema, at `line` of its file, or for the schema as a whole when zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl SchemaError {
+    pub(crate) fn new(line: usize, msg: String) -> Self {
+        SchemaError { line, msg }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "colfer: schema: {}", self.msg)
+        } else {
+            write!(f, "colfer: schema line {}: {}", self.line, self.msg)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for SchemaError {}
+
+pub type SchemaResult<T> = Result<T, SchemaError>;
+
+/// Data type of a field, or of its elements for lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Bool,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    Timestamp,
+    Text,
+    Binary,
+    /// Data structure declared in the schema.
+    Struct(TypeRef),
+}
+
+impl FieldType {
+    /// Looks up a built-in type by its name in schemas.
+    pub fn builtin(name: &str) -> Option<FieldType> {
+        Some(match name {
+            "bool" => FieldType::Bool,
+            "uint8" => FieldType::Uint8,
+            "uint16" => FieldType::Uint16,
+            "uint32" => FieldType::Uint32,
+            "uint64" => FieldType::Uint64,
+            "int32" => FieldType::Int32,
+            "int64" => FieldType::Int64,
+            "float32" => FieldType::Float32,
+            "float64" => FieldType::Float64,
+            "timestamp" => FieldType::Timestamp,
+            "text" => FieldType::Text,
+            "binary" => FieldType::Binary,
+            _ => return None,
+        })
+    }
+
+    /// Whether Colfer has lists of the type.
+    pub fn has_list(&self) -> bool {
+        matches!(
+            *self,
+            FieldType::Int32 | FieldType::Int64 | FieldType::Float32 | FieldType::Float64 | FieldType::Text | FieldType::Binary | FieldType::Struct(_)
+        )
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            FieldType::Bool => "bool",
+            FieldType::Uint8 => "uint8",
+            FieldType::Uint16 => "uint16",
+            FieldType::Uint32 => "uint32",
+            FieldType::Uint64 => "uint64",
+            FieldType::Int32 => "int32",
+            FieldType::Int64 => "int64",
+            FieldType::Float32 => "float32",
+            FieldType::Float64 => "float64",
+            FieldType::Timestamp => "timestamp",
+            FieldType::Text => "text",
+            FieldType::Binary => "binary",
+            FieldType::Struct(ref r) => return r.fmt(f),
+        })
+    }
+}
+
+/// Reference to a data structure, as written in the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeRef {
+    /// Package of the type, when qualified.
+    pub package: Option<String>,
+    pub name: String,
+}
+
+impl TypeRef {
+    /// Returns the package of the type, with `own` the package the reference is made from.
+    pub fn package_or<'s>(&'s self, own: &'s str) -> &'s str {
+        self.package.as_ref().map_or(own, |p| p.as_str())
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.package {
+            Some(ref p) => write!(f, "{}.{}", p, self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Comments around a declaration other than its doc comment, as kept for `format`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comments {
+    /// Blocks of comment lines before the doc comment, each with a blank line after it.
+    pub before: Vec<String>,
+    /// Comment at the end of the line, without the leading slashes.
+    pub trailing: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    /// Doc comment, without the leading slashes.
+    pub doc: String,
+    pub ty: FieldType,
+    pub list: bool,
+    /// Maximum size in bytes of a text or binary, or of each in a list, as set with the
+    /// `colfer:size_max=N` annotation.
+    pub size_max: Option<usize>,
+    /// Maximum number of elements of a list, as set with `colfer:list_max=N`.
+    pub list_max: Option<usize>,
+    /// Named values of a uint8 or uint16, as set with `colfer:enum=Name`.
+    pub enumeration: Option<Enum>,
+    /// Line of the declaration.
+    pub line: usize,
+    pub comments: Comments,
+}
+
+impl Field {
+    /// Doc comment without the annotations, nor the lines that only held annotations.
+    pub fn doc_text(&self) -> String {
+        let mut lines = Vec::new();
+        for line in self.doc.lines() {
+            if !line.contains("colfer:") {
+                lines.push(String::from(line));
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().filter(|w| !w.starts_with("colfer:")).collect();
+            if !words.is_empty() {
+                lines.push(words.join(" "));
+            }
+        }
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+}
+
+/// Named values of a uint8 or uint16 field, generated as a Rust enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum {
+    /// Name of the enum, as given with `colfer:enum=Name`.
+    pub name: String,
+    /// Names and values in order of declaration, as given with `colfer:values`.
+    pub values: Vec<(String, u16)>,
+    /// Whether values without a name decode as `Unknown`, as set with
+    /// `colfer:unknown=keep`, rather than failing.
+    pub keep_unknown: bool,
+}
+
+impl Enum {
+    /// Value for `Unknown` in the representation of the enum, which none of the named
+    /// values has.
+    pub fn unknown_value(&self) -> Option<u16> {
+        (0..=u16::MAX).find(|x| self.values.iter().all(|v| v.1 != *x))
+    }
+}
+
+/// Data structure declared with `type name struct`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    /// Doc comment, without the leading slashes.
+    pub doc: String,
+    /// Fields in index order.
+    pub fields: Vec<Field>,
+    /// Line of the declaration.
+    pub line: usize,
+    pub comments: Comments,
+    /// Comments after the last field, with the one on the line of the closing brace.
+    pub end: Comments,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /// Doc comment of the package clause, without the leading slashes.
+    pub doc: String,
+    pub types: Vec<Struct>,
+    /// Line of the package clause, in the first file of the package.
+    pub line: usize,
+    /// Comments around the package clause, in the first file of the package.
+    pub comments: Comments,
+    /// Blocks of comment lines after the last declaration.
+    pub end: Vec<String>,
+}
+
+impl Package {
+    /// Looks up a data structure by its name.
+    pub fn find(&self, name: &str) -> Option<&Struct> {
+        self.types.iter().find(|t| t.name == name)
+    }
+}
+
+/// Parses the content of a `.colf` file. References to other data structures are
+/// resolved by `Schema::resolve`.
+pub fn parse(src: &str) -> SchemaResult<Package> {
+    Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    }
+    .package()
+}
+
+/// Formats a parsed file the canonical way: one blank line between declarations, tab
+/// indents, and field types and trailing comments aligned in columns. All comments are
+/// kept, though blank lines only remain where they separate comment blocks. Comment
+/// blocks between fields get a blank line on either side.
+pub fn format(pkg: &Package) -> String {
+    let mut s = String::new();
+    blocks(&mut s, &pkg.comments.before, "");
+    comment(&mut s, &pkg.doc, "");
+    s.push_str("package ");
+    s.push_str(&pkg.name);
+    trailing(&mut s, &pkg.comments.trailing);
+
+    for t in pkg.types.iter() {
+        s.push('\n');
+        blocks(&mut s, &t.comments.before, "");
+        comment(&mut s, &t.doc, "");
+        s.push_str("type ");
+        s.push_str(&t.name);
+        s.push_str(" struct {");
+        trailing(&mut s, &t.comments.trailing);
+
+        let types: Vec<String> = t.fields.iter().map(|f| format!("{}{}", if f.list { "[]" } else { "" }, f.ty)).collect();
+        let name_width = t.fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        let type_width = types.iter().map(|t| t.len()).max().unwrap_or(0);
+        for (i, (f, ty)) in t.fields.iter().zip(types.iter()).enumerate() {
+            if i > 0 && !f.comments.before.is_empty() {
+                s.push('\n');
+            }
+            blocks(&mut s, &f.comments.before, "\t");
+            comment(&mut s, &f.doc, "\t");
+            let line = if f.comments.trailing.is_empty() {
+                format!("\t{:2$} {}", f.name, ty, name_width)
+            } else {
+                format!("\t{:2$} {:3$}", f.name, ty, name_width, type_width)
+            };
+            s.push_str(&line);
+            trailing(&mut s, &f.comments.trailing);
+        }
+
+        for (i, block) in t.end.before.iter().enumerate() {
+            if i > 0 {
+                s.push('\n');
+            }
+            comment(&mut s, block, "\t");
+        }
+        s.push('}');
+        trailing(&mut s, &t.end.trailing);
+    }
+
+    for block in pkg.end.iter() {
+        s.push('\n');
+        comment(&mut s, block, "");
+    }
+    s
+}
+
+// Writes the lines of a comment with the given indent.
+fn comment(s: &mut String, text: &str, indent: &str) {
+    if text.is_empty() {
+        return;
+    }
+    for line in text.split('\n') {
+        s.push_str(indent);
+        s.push_str("//");
+        if !line.is_empty() {
+            s.push(' ');
+            s.push_str(line);
+        }
+        s.push('\n');
+    }
+}
+
+fn blocks(s: &mut String, blocks: &[String], indent: &str) {
+    for block in blocks {
+        comment(s, block, indent);
+        s.push('\n');
+    }
+}
+
+// Ends the line, with the trailing comment if any.
+fn trailing(s: &mut String, text: &str) {
+    if !text.is_empty() {
+        s.push_str(" // ");
+        s.push_str(text);
+    }
+    s.push('\n');
+}
+
+/// The packages compiled together.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub packages: Vec<Package>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Schema::default()
+    }
+
+    /// Adds the package of a parsed file. The files of a package may declare its data
+    /// structures over several files, which get merged here, doc of the first file kept.
+    pub fn add(&mut self, mut pkg: Package) -> SchemaResult<()> {
+        let types = mem::take(&mut pkg.types);
+        let own = match self.packages.iter().position(|p| p.name == pkg.name) {
+            Some(i) => {
+                let own = &mut self.packages[i];
+                if own.doc.is_empty() {
+                    own.doc = pkg.doc;
+                }
+                own
+            }
+            None => {
+                self.packages.push(pkg);
+                self.packages.last_mut().unwrap()
+            }
+        };
+        for t in types {
+            if own.find(&t.name).is_some() {
+                return Err(SchemaError::new(t.line, format!("type {}.{} declared twice", own.name, t.name)));
+            }
+            own.types.push(t);
+        }
+        Ok(())
+    }
+
+    /// Looks up a package by its name.
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Looks up the data structure `r` refers to from package `from`.
+    pub fn lookup(&self, from: &str, r: &TypeRef) -> Option<(&Package, &Struct)> {
+        let pkg = self.package(r.package_or(from))?;
+        pkg.find(&r.name).map(|t| (pkg, t))
+    }
+
+    /// Checks that all references to data structures resolve and that no data structure
+    /// has more fields than the 127 headers Colfer has room for.
+    pub fn resolve(&self) -> SchemaResult<()> {
+        for pkg in self.packages.iter() {
+            for t in pkg.types.iter() {
+                if t.fields.len() > 127 {
+                    return Err(SchemaError::new(t.line, format!("type {}.{} has {} fields, more than 127", pkg.name, t.name, t.fields.len())));
+                }
+                for f in t.fields.iter() {
+                    if let FieldType::Struct(ref r) = f.ty {
+                        if self.lookup(&pkg.name, r).is_none() {
+                            return Err(SchemaError::new(f.line, format!("type {} of {}.{}.{} not found", r, pkg.name, t.name, f.name)));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Comment(String),
+    Dot,
+    List,
+    Open,
+    Close,
+}
+
+fn tokenize(src: &str) -> SchemaResult<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    for (i, text) in src.lines().enumerate() {
+        let line = i + 1;
+        let mut rest = text;
+        loop {
+            rest = rest.trim_start();
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => break,
+            };
+            let (token, n) = if let Some(comment) = rest.strip_prefix("//") {
+                // A single space after the slashes is part of the comment style.
+                let comment = comment.strip_prefix(' ').unwrap_or(comment);
+                (Token::Comment(comment.trim_end().to_string()), rest.len())
+            } else if rest.starts_with("[]") {
+                (Token::List, 2)
+            } else if c == '.' {
+                (Token::Dot, 1)
+            } else if c == '{' {
+                (Token::Open, 1)
+            } else if c == '}' {
+                (Token::Close, 1)
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let n = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                (Token::Ident(rest[..n].to_string()), n)
+            } else {
+                return Err(SchemaError::new(line, format!("unexpected character {:?}", c)));
+            };
+            tokens.push((token, line));
+            rest = &rest[n..];
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos) {
+            Some(&(_, line)) => line,
+            None => self.tokens.last().map_or(1, |&(_, line)| line),
+        }
+    }
+
+    // Collects the blocks of comment lines up to the next token. The last block is its
+    // doc comment when it ends on the line right above; the others come back as is.
+    fn comments(&mut self) -> (Vec<String>, String) {
+        let mut blocks = Vec::new();
+        let mut block: Vec<&str> = Vec::new();
+        let mut last = 0;
+        while let Some(&(Token::Comment(ref c), line)) = self.tokens.get(self.pos) {
+            if line != last + 1 && !block.is_empty() {
+                blocks.push(block.join("\n"));
+                block.clear();
+            }
+            block.push(c);
+            last = line;
+            self.pos += 1;
+        }
+        if block.is_empty() {
+            return (blocks, String::new());
+        }
+        if self.tokens.get(self.pos).is_some_and(|&(_, line)| line == last + 1) {
+            return (blocks, block.join("\n"));
+        }
+        blocks.push(block.join("\n"));
+        (blocks, String::new())
+    }
+
+    // Takes the comment at the end of the line of the last token, if any.
+    fn trailing(&mut self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(&(Token::Comment(ref c), line)) if self.pos > 0 && self.tokens[self.pos - 1].1 == line => {
+                let c = c.clone();
+                self.pos += 1;
+                c
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|t| t.0.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn ident(&mut self, what: &str) -> SchemaResult<String> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Ident(s)) => Ok(s),
+            _ => Err(SchemaError::new(line, format!("expected {}", what))),
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> SchemaResult<()> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Ident(ref s)) if s == word => Ok(()),
+            _ => Err(SchemaError::new(line, format!("expected {:?}", word))),
+        }
+    }
+
+    fn package(&mut self) -> SchemaResult<Package> {
+        let (before, doc) = self.comments();
+        let line = self.line();
+        self.keyword("package")?;
+        let name = self.ident("package name")?;
+        let comments = Comments {
+            before,
+            trailing: self.trailing(),
+        };
+
+        let mut types = Vec::new();
+        loop {
+            let (before, type_doc) = self.comments();
+            if self.pos >= self.tokens.len() {
+                return Ok(Package {
+                    name,
+                    doc,
+                    types,
+                    line,
+                    comments,
+                    end: before,
+                });
+            }
+            types.push(self.declaration(before, type_doc)?);
+        }
+    }
+
+    fn declaration(&mut self, before: Vec<String>, doc: String) -> SchemaResult<Struct> {
+        let line = self.line();
+        self.keyword("type")?;
+        let name = self.ident("type name")?;
+        self.keyword("struct")?;
+        if self.next() != Some(Token::Open) {
+            return Err(SchemaError::new(line, format!("expected {{ after type {}", name)));
+        }
+        let comments = Comments {
+            before,
+            trailing: self.trailing(),
+        };
+
+        let mut fields = Vec::new();
+        loop {
+            let (mut before, field_doc) = self.comments();
+            let field_line = self.line();
+            match self.next() {
+                Some(Token::Close) => {
+                    if !field_doc.is_empty() {
+                        before.push(field_doc);
+                    }
+                    let end = Comments {
+                        before,
+                        trailing: self.trailing(),
+                    };
+                    return Ok(Struct {
+                        name,
+                        doc,
+                        fields,
+                        line,
+                        comments,
+                        end,
+                    });
+                }
+                Some(Token::Ident(name)) => fields.push(self.field(name, before, field_doc, field_line)?),
+                _ => return Err(SchemaError::new(field_line, "expected field or }".to_string())),
+            }
+        }
+    }
+
+    fn field(&mut self, name: String, before: Vec<String>, doc: String, line: usize) -> SchemaResult<Field> {
+        let list = self.tokens.get(self.pos).is_some_and(|t| t.0 == Token::List);
+        if list {
+            self.pos += 1;
+        }
+        let first = self.ident("field type")?;
+        let ty = if self.tokens.get(self.pos).is_some_and(|t| t.0 == Token::Dot) {
+            self.pos += 1;
+            FieldType::Struct(TypeRef {
+                package: Some(first),
+                name: self.ident("type name")?,
+            })
+        } else {
+            FieldType::builtin(&first).unwrap_or(FieldType::Struct(TypeRef { package: None, name: first }))
+        };
+        if list && !ty.has_list() {
+            return Err(SchemaError::new(line, format!("field {}: Colfer has no lists of {}", name, ty)));
+        }
+        let mut f = Field {
+            name,
+            doc,
+            ty,
+            list,
+            size_max: None,
+            list_max: None,
+            enumeration: None,
+            line,
+            comments: Comments {
+                before,
+                trailing: self.trailing(),
+            },
+        };
+        annotate(&mut f)?;
+        Ok(f)
+    }
+}
+
+// Applies the annotations in the doc and trailing comment of a field, which are words
+// of the form colfer:key=value.
+fn annotate(f: &mut Field) -> SchemaResult<()> {
+    let words = f.doc.split_whitespace().chain(f.comments.trailing.split_whitespace());
+    let tags: Vec<String> = words.filter_map(|w| w.strip_prefix("colfer:")).map(String::from).collect();
+    let (line, name) = (f.line, f.name.clone());
+    let (mut enum_name, mut values, mut unknown) = (None, None, None);
+    for tag in tags {
+        let err = |msg: String| Err(SchemaError::new(line, format!("field {}: annotation colfer:{} {}", name, tag, msg)));
+        let (key, value) = tag.split_once('=').unwrap_or((&tag, ""));
+        let (max, limit) = match key {
+            "size_max" if f.ty == FieldType::Text || f.ty == FieldType::Binary => (&mut f.size_max, COLFER_SIZE_MAX),
+            "size_max" => return err(format!("on {}, while only text and binary have a size", f.ty)),
+            "list_max" if f.list => (&mut f.list_max, COLFER_LIST_MAX),
+            "list_max" => return err("on a field that is not a list".to_string()),
+            "enum" | "values" | "unknown" if f.list || (f.ty != FieldType::Uint8 && f.ty != FieldType::Uint16) => {
+                return err("on a field other than a uint8 or uint16".to_string())
+            }
+            "enum" | "values" | "unknown" => {
+                let set = match key {
+                    "enum" => &mut enum_name,
+                    "values" => &mut values,
+                    _ => &mut unknown,
+                };
+                if set.is_some() {
+                    return err("set twice".to_string());
+                }
+                *set = Some(value.to_string());
+                continue;
+            }
+            _ => return err("is unknown".to_string()),
+        };
+        match value.parse::<usize>() {
+            _ if max.is_some() => return err("set twice".to_string()),
+            Ok(n) if n != 0 && n <= limit => *max = Some(n),
+            _ => return err(format!("needs a number from 1 to {}", limit)),
+        }
+    }
+
+    let err = |msg: &str| Err(SchemaError::new(line, format!("field {}: {}", name, msg)));
+    let (enum_name, values) = match (enum_name, values) {
+        (Some(e), Some(v)) => (e, v),
+        (None, None) if unknown.is_none() => return Ok(()),
+        (None, _) => return err("annotations colfer:values and colfer:unknown need colfer:enum=Name"),
+        (Some(_), None) => return err("annotation colfer:enum needs colfer:values with the names"),
+    };
+    if !is_ident(&enum_name) {
+        return err("annotation colfer:enum needs a name");
+    }
+    let keep_unknown = match unknown.as_deref() {
+        None | Some("reject") => false,
+        Some("keep") => true,
+        Some(_) => return err("annotation colfer:unknown takes keep or reject"),
+    };
+
+    let max = if f.ty == FieldType::Uint8 { u8::MAX as u16 } else { u16::MAX };
+    let mut e = Enum {
+        name: enum_name,
+        values: Vec::new(),
+        keep_unknown,
+    };
+    let mut next = Some(0);
+    for v in values.split(',') {
+        let (value_name, x) = match v.split_once('=') {
+            Some((n, x)) => (n, x.parse::<u16>().ok()),
+            None => (v, next),
+        };
+        let x = match x {
+            Some(x) if x <= max => x,
+            _ => return err(&format!("enum value {} out of range of {}", value_name, f.ty)),
+        };
+        if !is_ident(value_name) || (keep_unknown && value_name.eq_ignore_ascii_case("unknown")) {
+            return err(&format!("enum value {:?} is not a usable name", value_name));
+        }
+        if e.values.iter().any(|o| o.0.eq_ignore_ascii_case(value_name) || o.1 == x) {
+            return err(&format!("enum value {} declared twice", value_name));
+        }
+        e.values.push((value_name.to_string(), x));
+        next = x.checked_add(1);
+    }
+    if !keep_unknown && e.values.iter().all(|v| v.1 != 0) {
+        return err("enum needs a value 0, which fields decode as when absent, or colfer:unknown=keep");
+    }
+    if keep_unknown && e.unknown_value().is_none_or(|x| x > max) {
+        return err("enum has no value left for Unknown");
+    }
+    f.enumeration = Some(e);
+    Ok(())
+}
+
+// Whether s can name an enum or its values: ASCII letters, digits and underscores,
+// starting with a letter.
+fn is_ident(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Canonical file with comments in every place format keeps them.
+    const FMT: &str = include_str!("../tests/fmt.colf");
+
+    #[test]
+    fn format_is_idempotent() {
+        assert_eq!(format(&parse(FMT).unwrap()), FMT);
+
+        // The same file laid out by hand, without the blank line after field y.
+        let messy = "// Package comment block that stands apart.\n\n\n\n\
+                     // Package fmt is formatted canonically.\n\
+                     package   fmt // trailing on the package\n\
+                     // Standalone comment before a type.\n\n\
+                     // Point is a location.\n\
+                     type point   struct { // trailing on the brace\n  \
+                       // X is the horizontal position.\n  \
+                       x float64 // trailing, aligned\n    \
+                         // Y is the vertical position.\n    \
+                         y float64\n    \
+                         // Standalone comment between fields.\n\n    \
+                         // Tags are labels.\n    \
+                         tags   []text // colfer:list_max=8\n        \
+                             // Commented-out field.\n    \
+                         }\n\
+                     // Comment block at the end of the file.\n";
+        let once = format(&parse(messy).unwrap());
+        assert_eq!(once, FMT);
+        assert_eq!(format(&parse(&once).unwrap()), once);
+    }
+}
@@ -0,0 +1,279 @@
+//! Storage of decoded fields. The wire logic of `ColferTypes` is written once, for its
+//! heap-backed fields, the fixed-capacity ones of `ColferTypesHeapless` and the
+//! arena-backed ones of `ColferTypesArena`, with the traits here doing what differs.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use super::{bulk,
+            cursor::Cursor,
+            error::{ColferError, ColferResult}};
+
+#[cfg(feature = "arena")]
+use bumpalo::collections::Vec as BumpVec;
+#[cfg(feature = "alloc")]
+use super::decode::get_list_len;
+#[cfg(feature = "heapless")]
+use super::decode::get_fixed_list_len;
+
+/// Text field, or element of a text list.
+pub trait Text<'a>: Default {
+    /// Sets the text to `x`, or fails when it doesn't fit.
+    fn set(&mut self, x: &'a str, field: &'static str) -> ColferResult<()>;
+}
+
+/// Binary field, or element of a binary list.
+pub trait Binary<'a>: Default {
+    /// Sets the binary to `x`, or fails when it doesn't fit.
+    fn set(&mut self, x: &'a [u8], field: &'static str) -> ColferResult<()>;
+    /// Empties the binary. Owned storage keeps its capacity.
+    #[cfg(feature = "arena")]
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// List field.
+pub trait List<T> {
+    /// Reads the length of the list, and fails when it exceeds the capacity.
+    fn read_len(&self, buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize>;
+    /// Clears the list, to hold `l` elements after.
+    fn refill(&mut self, l: usize);
+    /// Appends `x`, which fits as `read_len` checked the length.
+    fn add(&mut self, x: T);
+    /// Appends the big-endian floats of `data`, which fit as `read_len` checked their count.
+    fn add_floats(&mut self, data: &[u8])
+    where
+        T: Float;
+}
+
+/// Element of a floating point list.
+pub trait Float: Copy + Default {
+    #[cfg(feature = "alloc")]
+    fn extend(out: &mut Vec<Self>, data: &[u8]);
+    #[cfg(any(feature = "heapless", feature = "arena"))]
+    fn copy(out: &mut [Self], data: &[u8]);
+}
+
+impl Float for f32 {
+    #[cfg(feature = "alloc")]
+    fn extend(out: &mut Vec<f32>, data: &[u8]) {
+        bulk::extend_f32s(out, data)
+    }
+
+    #[cfg(any(feature = "heapless", feature = "arena"))]
+    fn copy(out: &mut [f32], data: &[u8]) {
+        bulk::copy_f32s(out, data)
+    }
+}
+
+impl Float for f64 {
+    #[cfg(feature = "alloc")]
+    fn extend(out: &mut Vec<f64>, data: &[u8]) {
+        bulk::extend_f64s(out, data)
+    }
+
+    #[cfg(any(feature = "heapless", feature = "arena"))]
+    fn copy(out: &mut [f64], data: &[u8]) {
+        bulk::copy_f64s(out, data)
+    }
+}
+
+impl<'a> Text<'a> for &'a str {
+    fn set(&mut self, x: &'a str, _field: &'static str) -> ColferResult<()> {
+        *self = x;
+        Ok(())
+    }
+}
+
+impl<'a> Binary<'a> for &'a [u8] {
+    fn set(&mut self, x: &'a [u8], _field: &'static str) -> ColferResult<()> {
+        *self = x;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> Binary<'a> for Vec<u8> {
+    fn set(&mut self, x: &'a [u8], _field: &'static str) -> ColferResult<()> {
+        self.clear();
+        self.extend_from_slice(x);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> List<T> for Vec<T> {
+    fn read_len(&self, buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize> {
+        get_list_len(buf, field, canonical)
+    }
+
+    fn refill(&mut self, l: usize) {
+        self.clear();
+        self.reserve(l);
+    }
+
+    fn add(&mut self, x: T) {
+        self.push(x);
+    }
+
+    fn add_floats(&mut self, data: &[u8])
+    where
+        T: Float,
+    {
+        T::extend(self, data);
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, const N: usize> Text<'a> for heapless::String<N> {
+    fn set(&mut self, x: &'a str, field: &'static str) -> ColferResult<()> {
+        self.clear();
+        self.push_str(x).map_err(|_| ColferError::MaxSizeBreach {
+            field,
+            overflow: x.len() - N,
+        })
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, const N: usize> Binary<'a> for heapless::Vec<u8, N> {
+    fn set(&mut self, x: &'a [u8], field: &'static str) -> ColferResult<()> {
+        self.clear();
+        self.extend_from_slice(x).map_err(|_| ColferError::MaxSizeBreach {
+            field,
+            overflow: x.len() - N,
+        })
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> List<T> for heapless::Vec<T, N> {
+    fn read_len(&self, buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize> {
+        get_fixed_list_len::<N>(buf, field, canonical)
+    }
+
+    fn refill(&mut self, _l: usize) {
+        self.clear();
+    }
+
+    fn add(&mut self, x: T) {
+        let _ = self.push(x);
+    }
+
+    fn add_floats(&mut self, data: &[u8])
+    where
+        T: Float,
+    {
+        let len = self.len();
+        let n = data.len() / core::mem::size_of::<T>();
+        let _ = self.resize_default(len + n);
+        T::copy(&mut self[len..], data);
+    }
+}
+
+#[cfg(feature = "arena")]
+impl<'a, T> List<T> for BumpVec<'a, T> {
+    fn read_len(&self, buf: &mut Cursor, field: &'static str, canonical: bool) -> ColferResult<usize> {
+        get_list_len(buf, field, canonical)
+    }
+
+    fn refill(&mut self, l: usize) {
+        self.clear();
+        self.reserve(l);
+    }
+
+    fn add(&mut self, x: T) {
+        self.push(x);
+    }
+
+    fn add_floats(&mut self, data: &[u8])
+    where
+        T: Float,
+    {
+        let len = self.len();
+        let n = data.len() / core::mem::size_of::<T>();
+        self.resize(len + n, T::default());
+        T::copy(&mut self[len..], data);
+    }
+}
+
+/// Splits the `count` texts at `data[start..]` into `out` and returns the number of
+/// bytes read, like `bulk::split_texts`.
+pub fn split_texts<'a, T, L>(data: &'a [u8], start: usize, count: usize, field: &'static str, canonical: bool, out: &mut L) -> ColferResult<usize>
+where
+    T: Text<'a>,
+    L: List<T>,
+{
+    let mut fill = Filler::new(out, field);
+    let n = bulk::split_texts(data, start, count, field, canonical, &mut fill)?;
+    fill.done().map(|_| n)
+}
+
+/// Splits the `count` binaries at `data[start..]` into `out` and returns the number of
+/// bytes read, like `bulk::split_binaries`.
+pub fn split_binaries<'a, T, L>(data: &'a [u8], start: usize, count: usize, field: &'static str, canonical: bool, out: &mut L) -> ColferResult<usize>
+where
+    T: Binary<'a>,
+    L: List<T>,
+{
+    let mut fill = Filler::new(out, field);
+    let n = bulk::split_binaries(data, start, count, field, canonical, &mut fill)?;
+    fill.done().map(|_| n)
+}
+
+// Appends split elements to a list, converted to its element type. Extend can't fail, so
+// the first error is kept for done.
+struct Filler<'l, T, L: 'l> {
+    list: &'l mut L,
+    field: &'static str,
+    err: Option<ColferError>,
+    elem: PhantomData<T>,
+}
+
+impl<'l, T, L: List<T>> Filler<'l, T, L> {
+    fn new(list: &'l mut L, field: &'static str) -> Self {
+        Filler {
+            list,
+            field,
+            err: None,
+            elem: PhantomData,
+        }
+    }
+
+    fn done(self) -> ColferResult<()> {
+        match self.err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, 'l, T: Text<'a>, L: List<T>> Extend<&'a str> for Filler<'l, T, L> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for x in iter {
+            let mut t = T::default();
+            match t.set(x, self.field) {
+                Ok(()) => self.list.add(t),
+                Err(e) => {
+                    self.err.get_or_insert(e);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, 'l, T: Binary<'a>, L: List<T>> Extend<&'a [u8]> for Filler<'l, T, L> {
+    fn extend<I: IntoIterator<Item = &'a [u8]>>(&mut self, iter: I) {
+        for x in iter {
+            let mut t = T::default();
+            match t.set(x, self.field) {
+                Ok(()) => self.list.add(t),
+                Err(e) => {
+                    self.err.get_or_insert(e);
+                }
+            }
+        }
+    }
+}
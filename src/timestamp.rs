@@ -0,0 +1,181 @@
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+#[cfg(any(feature = "std", feature = "chrono", feature = "time"))]
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "time")]
+use time::OffsetDateTime;
+
+#[cfg(any(feature = "std", feature = "chrono", feature = "time"))]
+use super::error::ColferError;
+
+/// Point in time as Colfer encodes it: seconds since the Unix epoch and nanoseconds into
+/// the second. Unlike `SystemTime`, it covers the full `int64` range of seconds on every
+/// platform, and it doesn't need `std`. Encoding and decoding round-trip every value.
+///
+/// Conversions to `SystemTime`, `chrono::DateTime<Utc>` and `time::OffsetDateTime`, with
+/// the `std`, `chrono` and `time` features, fail with `ColferError::TimestampRange` for
+/// values outside the range of the target type. The other way around can't fail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    /// Seconds since 1970-01-01T00:00:00Z, negative before.
+    pub seconds: i64,
+    /// Nanoseconds into the second, below 1 000 000 000.
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    /// 1970-01-01T00:00:00Z, the zero value.
+    pub const UNIX_EPOCH: Timestamp = Timestamp { seconds: 0, nanos: 0 };
+
+    pub fn new(seconds: i64, nanos: u32) -> Self {
+        Timestamp { seconds, nanos }
+    }
+
+    /// Whether this is the zero value, which encodes as an absent field.
+    pub fn is_zero(&self) -> bool {
+        self.seconds == 0 && self.nanos == 0
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SystemTime> for Timestamp {
+    fn from(t: SystemTime) -> Self {
+        match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => Timestamp::new(d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                match d.subsec_nanos() {
+                    0 => Timestamp::new((d.as_secs() as i64).wrapping_neg(), 0),
+                    ns => Timestamp::new((d.as_secs() as i64).wrapping_neg() - 1, 1_000_000_000 - ns),
+                }
+            }
+        }
+    }
+}
+
+/// Fails with `ColferError::TimestampRange` when the platform's `SystemTime` can't hold
+/// the value.
+#[cfg(feature = "std")]
+impl TryFrom<Timestamp> for SystemTime {
+    type Error = ColferError;
+
+    fn try_from(t: Timestamp) -> Result<Self, Self::Error> {
+        let secs = Duration::from_secs(t.seconds.unsigned_abs());
+        let t2 = if t.seconds < 0 {
+            UNIX_EPOCH.checked_sub(secs)
+        } else {
+            UNIX_EPOCH.checked_add(secs)
+        };
+        t2.and_then(|t2| t2.checked_add(Duration::from_nanos(u64::from(t.nanos))))
+            .ok_or(ColferError::TimestampRange)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(t: DateTime<Utc>) -> Self {
+        // Leap seconds have their nanoseconds past 999 999 999, which Colfer doesn't allow,
+        // so they fold into the next second.
+        let (s, ns) = (t.timestamp(), t.timestamp_subsec_nanos());
+        if ns >= 1_000_000_000 {
+            Timestamp::new(s + 1, ns - 1_000_000_000)
+        } else {
+            Timestamp::new(s, ns)
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Timestamp> for DateTime<Utc> {
+    type Error = ColferError;
+
+    fn try_from(t: Timestamp) -> Result<Self, Self::Error> {
+        if t.nanos >= 1_000_000_000 {
+            return Err(ColferError::TimestampRange);
+        }
+        DateTime::from_timestamp(t.seconds, t.nanos).ok_or(ColferError::TimestampRange)
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<OffsetDateTime> for Timestamp {
+    fn from(t: OffsetDateTime) -> Self {
+        Timestamp::new(t.unix_timestamp(), t.nanosecond())
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Timestamp> for OffsetDateTime {
+    type Error = ColferError;
+
+    fn try_from(t: Timestamp) -> Result<Self, Self::Error> {
+        let ns = i128::from(t.seconds) * 1_000_000_000 + i128::from(t.nanos);
+        OffsetDateTime::from_unix_timestamp_nanos(ns).map_err(|_| ColferError::TimestampRange)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Half a second before the epoch.
+    #[cfg(any(feature = "std", feature = "chrono", feature = "time"))]
+    const BEFORE: Timestamp = Timestamp {
+        seconds: -1,
+        nanos: 500_000_000,
+    };
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time() {
+        assert_eq!(SystemTime::try_from(Timestamp::UNIX_EPOCH).unwrap(), UNIX_EPOCH);
+        assert_eq!(Timestamp::from(UNIX_EPOCH), Timestamp::UNIX_EPOCH);
+
+        let t = SystemTime::try_from(BEFORE).unwrap();
+        assert_eq!(t, UNIX_EPOCH - Duration::from_millis(500));
+        assert_eq!(Timestamp::from(t), BEFORE);
+        assert_eq!(Timestamp::from(UNIX_EPOCH - Duration::from_secs(2)), Timestamp::new(-2, 0));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono() {
+        let epoch = DateTime::<Utc>::try_from(Timestamp::UNIX_EPOCH).unwrap();
+        assert_eq!(epoch, DateTime::UNIX_EPOCH);
+        assert_eq!(Timestamp::from(DateTime::UNIX_EPOCH), Timestamp::UNIX_EPOCH);
+
+        let t = DateTime::<Utc>::try_from(BEFORE).unwrap();
+        assert_eq!(t, DateTime::from_timestamp_millis(-500).unwrap());
+        assert_eq!(Timestamp::from(t), BEFORE);
+
+        for &s in [i64::MIN, i64::MAX].iter() {
+            let r = DateTime::<Utc>::try_from(Timestamp::new(s, 0));
+            assert!(matches!(r, Err(ColferError::TimestampRange)), "{}: {:?}", s, r);
+        }
+        let r = DateTime::<Utc>::try_from(Timestamp::new(0, 1_000_000_000));
+        assert!(matches!(r, Err(ColferError::TimestampRange)), "{:?}", r);
+
+        // A leap second folds into the next second.
+        let leap = DateTime::from_timestamp(59, 1_250_000_000).unwrap();
+        assert_eq!(Timestamp::from(leap), Timestamp::new(60, 250_000_000));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time() {
+        let epoch = OffsetDateTime::try_from(Timestamp::UNIX_EPOCH).unwrap();
+        assert_eq!(epoch, OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(Timestamp::from(OffsetDateTime::UNIX_EPOCH), Timestamp::UNIX_EPOCH);
+
+        let t = OffsetDateTime::try_from(BEFORE).unwrap();
+        assert_eq!(t, OffsetDateTime::from_unix_timestamp_nanos(-500_000_000).unwrap());
+        assert_eq!(Timestamp::from(t), BEFORE);
+
+        for &s in [i64::MIN, i64::MAX].iter() {
+            let r = OffsetDateTime::try_from(Timestamp::new(s, 999_999_999));
+            assert!(matches!(r, Err(ColferError::TimestampRange)), "{}: {:?}", s, r);
+        }
+    }
+}
@@ -1,61 +1,84 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::{descriptor::{ColferType, FieldDescriptor, MessageDescriptor},
+            error::ColferResult,
+            timestamp::Timestamp,
+            view::ColferView};
+
+// What the wire logic of the decoded variants builds on.
+#[cfg(any(feature = "alloc", feature = "heapless"))]
 use bytes::{Buf, BufMut};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
-// TODO: unmarshal
-use super::{error::{ColferError, ColferResult},
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+use super::{cursor::Cursor,
+            decode::{get_binary, get_text, get_timestamp, get_varint32, get_varint64, skip_field},
+            error::ColferError,
+            options::{DecodeOptions, UnknownFields},
+            store::{self, Binary, List, Text},
+            varint,
             ColferSerializable,
             COLFER_LIST_MAX,
             COLFER_SIZE_MAX};
 
-macro_rules! buf_guard {
-    ($buf:ident) => {
-        if !$buf.has_remaining() {
-            return Err(ColferError::UnexpectedEof);
-        }
-    };
-}
+#[cfg(feature = "parallel")]
+use core::mem;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "arena")]
+use bumpalo::{boxed::Box as BumpBox, collections::Vec as BumpVec, Bump};
 
 /// Contains all supported data types.
+#[cfg(feature = "alloc")]
 #[allow(non_snake_case)]
 pub struct ColferTypes<'a> {
     /// B tests booleans.
-    B: bool,
+    pub B: bool,
     /// U32 tests unsigned 32-bit integers.
-    U32: u32,
+    pub U32: u32,
     /// U64 tests unsigned 64-bit integers.
-    U64: u64,
+    pub U64: u64,
     /// I32 tests signed 32-bit integers.
-    I32: i32,
+    pub I32: i32,
     /// I64 tests signed 64-bit integers.
-    I64: i64,
+    pub I64: i64,
     /// F64 tests 64-bit floating points.
-    F64: f64,
+    pub F64: f64,
     /// F32 tests 32-bit floating points.
-    F32: f32,
+    pub F32: f32,
     /// T tests timestamps.
-    T: SystemTime,
+    pub T: Timestamp,
     /// S tests text.
-    S: &'a str,
+    pub S: &'a str,
     /// A tests binaries.
-    A: Vec<u8>,
+    pub A: Vec<u8>,
     /// O tests nested data structures.
-    O: Option<Box<ColferTypes<'a>>>,
+    pub O: Option<Box<ColferTypes<'a>>>,
     /// Os tests data structure lists.
-    Os: Vec<Option<ColferTypes<'a>>>,
+    pub Os: Vec<Option<ColferTypes<'a>>>,
     /// Ss tests text lists.
-    Ss: Vec<&'a str>,
+    pub Ss: Vec<&'a str>,
     /// As tests binary lists.
-    As: Vec<&'a [u8]>,
+    pub As: Vec<&'a [u8]>,
     /// U8 tests unsigned 8-bit integers.
-    U8: u8,
+    pub U8: u8,
     /// U16 tests unsigned 16-bit integers.
-    U16: u16,
+    pub U16: u16,
     /// F32s tests 32-bit floating point lists.
-    F32s: Vec<f32>,
+    pub F32s: Vec<f32>,
     /// F64s tests 64-bit floating point lists.
-    F64s: Vec<f64>,
+    pub F64s: Vec<f64>,
+    /// I32s tests signed 32-bit integer lists.
+    pub I32s: Vec<i32>,
+    /// I64s tests signed 64-bit integer lists.
+    pub I64s: Vec<i64>,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
 }
 
+#[cfg(feature = "alloc")]
 impl<'a> Default for ColferTypes<'a> {
     fn default() -> Self {
         ColferTypes {
@@ -66,7 +89,7 @@ impl<'a> Default for ColferTypes<'a> {
             I64: i64::default(),
             F64: f64::default(),
             F32: f32::default(),
-            T: SystemTime::now(),
+            T: Timestamp::UNIX_EPOCH,
             S: "",
             A: Vec::default(),
             O: Option::default(),
@@ -77,737 +100,1835 @@ impl<'a> Default for ColferTypes<'a> {
             U16: u16::default(),
             F32s: Vec::default(),
             F64s: Vec::default(),
+            I32s: Vec::default(),
+            I64s: Vec::default(),
+            unknown_fields: &[],
         }
     }
 }
 
-impl<'a> ColferSerializable<'a> for ColferTypes<'a> {
-    // MarshalTo encodes o as Colfer into buf and returns the number of bytes written.
-    fn colf_marshal_to(&self, buf: &mut Vec<u8>) -> usize {
-        let previous_len = buf.len();
-        if self.B {
-            buf.put_u8(0);
-        }
-
-        {
-            let mut x = self.U32;
-            if x >= 1 << 21 {
-                buf.put_u8(1 | 0x80);
-                buf.put_u32_be(x);
-            } else if x != 0 {
-                buf.put_u8(1);
-                while x >= 0x80 {
-                    buf.put_u8(x as u8 | 0x80);
-                    x >>= 7;
-                }
-                buf.put_u8(x as u8);
+static COLFER_TYPES: MessageDescriptor = MessageDescriptor {
+    name: "ColferTypes",
+    doc: "Contains all supported data types.",
+    fields: &[
+        FieldDescriptor {
+            name: "B",
+            index: 0,
+            ty: ColferType::Bool,
+            list: false,
+            doc: "B tests booleans.",
+        },
+        FieldDescriptor {
+            name: "U32",
+            index: 1,
+            ty: ColferType::Uint32,
+            list: false,
+            doc: "U32 tests unsigned 32-bit integers.",
+        },
+        FieldDescriptor {
+            name: "U64",
+            index: 2,
+            ty: ColferType::Uint64,
+            list: false,
+            doc: "U64 tests unsigned 64-bit integers.",
+        },
+        FieldDescriptor {
+            name: "I32",
+            index: 3,
+            ty: ColferType::Int32,
+            list: false,
+            doc: "I32 tests signed 32-bit integers.",
+        },
+        FieldDescriptor {
+            name: "I64",
+            index: 4,
+            ty: ColferType::Int64,
+            list: false,
+            doc: "I64 tests signed 64-bit integers.",
+        },
+        FieldDescriptor {
+            name: "F32",
+            index: 5,
+            ty: ColferType::Float32,
+            list: false,
+            doc: "F32 tests 32-bit floating points.",
+        },
+        FieldDescriptor {
+            name: "F64",
+            index: 6,
+            ty: ColferType::Float64,
+            list: false,
+            doc: "F64 tests 64-bit floating points.",
+        },
+        FieldDescriptor {
+            name: "T",
+            index: 7,
+            ty: ColferType::Timestamp,
+            list: false,
+            doc: "T tests timestamps.",
+        },
+        FieldDescriptor {
+            name: "S",
+            index: 8,
+            ty: ColferType::Text,
+            list: false,
+            doc: "S tests text.",
+        },
+        FieldDescriptor {
+            name: "A",
+            index: 9,
+            ty: ColferType::Binary,
+            list: false,
+            doc: "A tests binaries.",
+        },
+        FieldDescriptor {
+            name: "O",
+            index: 10,
+            ty: ColferType::Struct(&COLFER_TYPES),
+            list: false,
+            doc: "O tests nested data structures.",
+        },
+        FieldDescriptor {
+            name: "Os",
+            index: 11,
+            ty: ColferType::Struct(&COLFER_TYPES),
+            list: true,
+            doc: "Os tests data structure lists.",
+        },
+        FieldDescriptor {
+            name: "Ss",
+            index: 12,
+            ty: ColferType::Text,
+            list: true,
+            doc: "Ss tests text lists.",
+        },
+        FieldDescriptor {
+            name: "As",
+            index: 13,
+            ty: ColferType::Binary,
+            list: true,
+            doc: "As tests binary lists.",
+        },
+        FieldDescriptor {
+            name: "U8",
+            index: 14,
+            ty: ColferType::Uint8,
+            list: false,
+            doc: "U8 tests unsigned 8-bit integers.",
+        },
+        FieldDescriptor {
+            name: "U16",
+            index: 15,
+            ty: ColferType::Uint16,
+            list: false,
+            doc: "U16 tests unsigned 16-bit integers.",
+        },
+        FieldDescriptor {
+            name: "F32s",
+            index: 16,
+            ty: ColferType::Float32,
+            list: true,
+            doc: "F32s tests 32-bit floating point lists.",
+        },
+        FieldDescriptor {
+            name: "F64s",
+            index: 17,
+            ty: ColferType::Float64,
+            list: true,
+            doc: "F64s tests 64-bit floating point lists.",
+        },
+        FieldDescriptor {
+            name: "I32s",
+            index: 18,
+            ty: ColferType::Int32,
+            list: true,
+            doc: "I32s tests signed 32-bit integer lists.",
+        },
+        FieldDescriptor {
+            name: "I64s",
+            index: 19,
+            ty: ColferType::Int64,
+            list: true,
+            doc: "I64s tests signed 64-bit integer lists.",
+        },
+    ],
+};
+
+// Implements the wire format for a struct with the fields of ColferTypes, whatever their
+// storage: the traits of the store module and Nested cover what differs.
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+macro_rules! colfer_types_wire {
+    ([$($gen:tt)*] $ty:ty) => {
+        impl<'a, $($gen)*> ColferSerializable<'a> for $ty {
+            const DESCRIPTOR: &'static MessageDescriptor = &COLFER_TYPES;
+
+            // MarshalTo encodes o as Colfer into buf and returns the number of bytes written.
+            fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+                let remaining = buf.remaining_mut();
+                self.marshal_head(buf);
+                self.Os.put(buf);
+                self.marshal_tail(buf);
+                remaining - buf.remaining_mut()
             }
-        }
 
-        {
-            let mut x = self.U64;
-            if x >= 1 << 49 {
-                buf.put_u8(2 | 0x80);
-                buf.put_u64_be(x);
-            } else if x != 0 {
-                buf.put_u8(2);
-                while x >= 0x80 {
-                    buf.put_u8(x as u8 | 0x80);
-                    x >>= 7;
-                }
-                buf.put_u8(x as u8);
+            fn colf_marshal_len(&self) -> ColferResult<usize> {
+                self.marshal_len(None)
             }
-        }
 
-        if self.I32 != 0 {
-            let mut x = self.I32 as u32;
-            if self.I32 > 0 {
-                buf.put_u8(3);
-            } else {
-                x = !x + 1;
-                buf.put_u8(3 | 0x80);
+            fn colf_reset(&mut self) {
+                self.reset_values();
+                self.O = None;
+                self.Os.clear();
             }
 
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
+            fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+                self.unmarshal(data, opts, false)
             }
 
-            buf.put_u8(x as u8);
+            fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+                self.unmarshal(data, &DecodeOptions::default(), true)
+            }
         }
 
-        if self.I64 != 0 {
-            let mut x = self.I64 as u64;
-            if self.I64 > 0 {
-                buf.put_u8(4);
-            } else {
-                x = !x + 1;
-                buf.put_u8(4 | 0x80);
+        impl<'a, $($gen)*> $ty {
+            // Resets all fields but O and Os, which colf_unmarshal_into decodes into instead.
+            fn reset_values(&mut self) {
+                self.B = false;
+                self.U32 = 0;
+                self.U64 = 0;
+                self.I32 = 0;
+                self.I64 = 0;
+                self.F64 = 0.0;
+                self.F32 = 0.0;
+                self.T = Timestamp::UNIX_EPOCH;
+                self.S = Default::default();
+                self.A.clear();
+                self.Ss.clear();
+                self.As.clear();
+                self.U8 = 0;
+                self.U16 = 0;
+                self.F32s.clear();
+                self.F64s.clear();
+                self.I32s.clear();
+                self.I64s.clear();
+                self.unknown_fields = &[];
             }
 
-            for _ in 0..8 {
-                if x < 0x80 {
-                    break;
+            // Writes the fields before the elements of Os, up to and including its length.
+            fn marshal_head<B: BufMut>(&self, buf: &mut B) {
+                if self.B {
+                    buf.put_u8(0);
                 }
 
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
+                {
+                    let x = self.U32;
+                    if x >= 1 << 21 {
+                        buf.put_u8(1 | 0x80);
+                        buf.put_u32(x);
+                    } else if x != 0 {
+                        buf.put_u8(1);
+                        varint::put_u32(buf, x);
+                    }
+                }
 
-            buf.put_u8(x as u8);
-        }
+                {
+                    let x = self.U64;
+                    if x >= 1 << 49 {
+                        buf.put_u8(2 | 0x80);
+                        buf.put_u64(x);
+                    } else if x != 0 {
+                        buf.put_u8(2);
+                        varint::put_u64(buf, x);
+                    }
+                }
 
-        if self.F32 != 0.0 {
-            buf.put_u8(5);
-            buf.put_u32_be(self.F32.to_bits());
-        }
+                if self.I32 != 0 {
+                    if self.I32 > 0 {
+                        buf.put_u8(3);
+                    } else {
+                        buf.put_u8(3 | 0x80);
+                    }
+                    varint::put_u32(buf, self.I32.unsigned_abs());
+                }
 
-        if self.F64 != 0.0 {
-            buf.put_u8(6);
-            buf.put_u64_be(self.F64.to_bits());
-        }
+                if self.I64 != 0 {
+                    if self.I64 > 0 {
+                        buf.put_u8(4);
+                    } else {
+                        buf.put_u8(4 | 0x80);
+                    }
+                    varint::put_u64(buf, self.I64.unsigned_abs());
+                }
 
-        if self.T != UNIX_EPOCH {
-            // Safe unwrap since we checked if it didn't match UNIX_EPOCH
-            let dur = self.T.duration_since(UNIX_EPOCH).unwrap();
-            let s = dur.as_secs();
-            if s < 1 << 32 {
-                buf.put_u8(7);
-                buf.put_u32_be(s as u32);
-            } else {
-                buf.put_u8(7 | 0x80);
-                buf.put_u64_be(s);
-            }
+                if self.F32 != 0.0 {
+                    buf.put_u8(5);
+                    buf.put_u32(self.F32.to_bits());
+                }
 
-            buf.put_u32_be(dur.subsec_nanos());
-        }
+                if self.F64 != 0.0 {
+                    buf.put_u8(6);
+                    buf.put_u64(self.F64.to_bits());
+                }
 
-        if self.S.len() > 0 {
-            buf.put_u8(8);
-            let mut x = self.S.len();
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
-            buf.put_u8(x as u8);
-            buf.put_slice(&self.S.as_bytes());
-        }
+                if !self.T.is_zero() {
+                    // Seconds outside the range of a uint32 take the wide form, as an int64.
+                    let s = self.T.seconds;
+                    if (0..1 << 32).contains(&s) {
+                        buf.put_u8(7);
+                        buf.put_u32(s as u32);
+                    } else {
+                        buf.put_u8(7 | 0x80);
+                        buf.put_i64(s);
+                    }
 
-        if self.A.len() > 0 {
-            buf.put_u8(9);
-            let mut x = self.A.len();
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
-            buf.put_u8(x as u8);
-            buf.put_slice(&self.A);
-        }
+                    buf.put_u32(self.T.nanos);
+                }
 
-        if let Some(ref o) = self.O {
-            buf.put_u8(10);
-            o.colf_marshal_to(buf);
-        }
+                if !self.S.is_empty() {
+                    buf.put_u8(8);
+                    varint::put_usize(buf, self.S.len());
+                    buf.put_slice(self.S.as_bytes());
+                }
 
-        if self.Os.len() > 0 {
-            let len = self.Os.len();
-            if len > 0 {
-                buf.put_u8(11);
-                let mut x = len as u32;
-                while x >= 0x80 {
-                    buf.put_u8(x as u8 | 0x80);
-                    x >>= 7;
+                if !self.A.is_empty() {
+                    buf.put_u8(9);
+                    varint::put_usize(buf, self.A.len());
+                    buf.put_slice(&self.A);
                 }
-                buf.put_u8(x as u8);
 
-                for vio in self.Os.iter() {
-                    if let Some(ref vi) = vio {
-                        vi.colf_marshal_to(buf);
-                    }
+                if let Some(ref o) = self.O {
+                    buf.put_u8(10);
+                    o.put(buf);
                 }
-            }
-        }
 
-        if self.Ss.len() > 0 {
-            buf.put_u8(12);
-            let mut x = self.Ss.len() as u32;
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
+                if !self.Os.is_empty() {
+                    buf.put_u8(11);
+                    varint::put_usize(buf, self.Os.len());
+                }
             }
-            buf.put_u8(x as u8);
 
-            for s in self.Ss.iter() {
-                let mut xs = s.len() as u32;
-                while xs >= 0x80 {
-                    buf.put_u8(xs as u8 | 0x80);
-                    xs >>= 7;
+            // Writes the fields after the elements of Os, and the terminator.
+            fn marshal_tail<B: BufMut>(&self, buf: &mut B) {
+                if !self.Ss.is_empty() {
+                    buf.put_u8(12);
+                    varint::put_usize(buf, self.Ss.len());
+                    for s in self.Ss.iter() {
+                        varint::put_usize(buf, s.len());
+                        buf.put_slice(s.as_bytes());
+                    }
                 }
-                buf.put_u8(xs as u8);
 
-                buf.put_slice(&s.as_bytes());
-            }
-        }
-        if self.As.len() > 0 {
-            buf.put_u8(13);
-            let mut x = self.As.len() as u32;
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
-            buf.put_u8(x as u8);
+                if !self.As.is_empty() {
+                    buf.put_u8(13);
+                    varint::put_usize(buf, self.As.len());
+                    for a in self.As.iter() {
+                        varint::put_usize(buf, a.len());
+                        buf.put_slice(a);
+                    }
+                }
 
-            for a in self.As.iter() {
-                let mut xs = a.len() as u32;
-                while xs >= 0x80 {
-                    buf.put_u8(xs as u8 | 0x80);
-                    xs >>= 7;
+                if self.U8 > 0 {
+                    buf.put_u8(14);
+                    buf.put_u8(self.U8);
                 }
-                buf.put_u8(xs as u8);
 
-                buf.put_slice(&a);
-            }
-        }
+                if self.U16 >= 1 << 8 {
+                    buf.put_u8(15);
+                    buf.put_u8((self.U16 >> 8) as u8);
+                    buf.put_u8(self.U16 as u8);
+                } else if self.U16 != 0 {
+                    buf.put_u8(15 | 0x80);
+                    buf.put_u8(self.U16 as u8);
+                }
 
-        if self.U8 > 0 {
-            buf.put_u8(14);
-            buf.put_u8(self.U8);
-        }
+                if !self.F32s.is_empty() {
+                    buf.put_u8(16);
+                    varint::put_usize(buf, self.F32s.len());
+                    for f in self.F32s.iter() {
+                        buf.put_u32(f.to_bits());
+                    }
+                }
 
-        if self.U16 >= 1 << 8 {
-            buf.put_u8(15);
-            buf.put_u8((self.U16 >> 8) as u8);
-            buf.put_u8(self.U16 as u8);
-        } else if self.U16 != 0 {
-            buf.put_u8(15 | 0x80);
-            buf.put_u8(self.U16 as u8);
-        }
+                if !self.F64s.is_empty() {
+                    buf.put_u8(17);
+                    varint::put_usize(buf, self.F64s.len());
+                    for f in self.F64s.iter() {
+                        buf.put_u64(f.to_bits());
+                    }
+                }
 
-        if self.F32s.len() > 0 {
-            buf.put_u8(16);
-            let mut x = self.F32s.len();
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
-            buf.put_u8(x as u8);
-            for f in self.F32s.iter() {
-                buf.put_u32_be(f.to_bits());
-            }
-        }
+                if !self.I32s.is_empty() {
+                    buf.put_u8(18);
+                    varint::put_usize(buf, self.I32s.len());
+                    for &x in self.I32s.iter() {
+                        varint::put_u32(buf, varint::zigzag_i32(x));
+                    }
+                }
 
-        if self.F64s.len() > 0 {
-            buf.put_u8(17);
-            let mut x = self.F64s.len();
-            while x >= 0x80 {
-                buf.put_u8(x as u8 | 0x80);
-                x >>= 7;
-            }
-            buf.put_u8(x as u8);
-            for f in self.F64s.iter() {
-                buf.put_u64_be(f.to_bits());
+                if !self.I64s.is_empty() {
+                    buf.put_u8(19);
+                    varint::put_usize(buf, self.I64s.len());
+                    for &x in self.I64s.iter() {
+                        varint::put_u64(buf, varint::zigzag_i64(x));
+                    }
+                }
+
+                buf.put_slice(self.unknown_fields);
+                buf.put_u8(0x7F);
             }
-        }
 
-        buf.put_u8(0x7F);
-        buf.len() - previous_len
-    }
+            // Computes colf_marshal_len, with the sizes of the elements of Os given when known.
+            fn marshal_len(&self, os_sizes: Option<&[usize]>) -> ColferResult<usize> {
+                let mut l = 1 + self.unknown_fields.len();
+                if self.B {
+                    l += 1;
+                }
 
-    fn colf_marshal_len(&self) -> ColferResult<usize> {
-        let mut l = 1;
-        if self.B {
-            l += 1;
-        }
+                {
+                    let x = self.U32;
+                    if x >= 1 << 21 {
+                        l += 5;
+                    } else if x != 0 {
+                        l += 1 + varint::size_u32(x);
+                    }
+                }
 
-        {
-            let mut x = self.U32;
-            if x >= 1 << 21 {
-                l += 5;
-            } else if x != 0 {
-                l += 2;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
+                {
+                    let x = self.U64;
+                    if x >= 1 << 49 {
+                        l += 9;
+                    } else if x != 0 {
+                        l += 1 + varint::size_u64(x);
+                    }
                 }
-            }
-        }
 
-        {
-            let mut x = self.U64;
-            if x >= 1 << 49 {
-                l += 9;
-            } else if x != 0 {
-                l += 2;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
+                if self.I32 != 0 {
+                    l += 1 + varint::size_u32(self.I32.unsigned_abs());
                 }
-            }
-        }
 
-        if self.I32 != 0 {
-            let mut x = self.I32 as u32;
-            if self.I32 < 0 {
-                x = !x + 1;
-            }
-            l += 2;
-            while x >= 0x80 {
-                x >>= 7;
-                l += 1;
-            }
-        }
+                if self.I64 != 0 {
+                    l += 1 + varint::size_u64(self.I64.unsigned_abs());
+                }
 
-        if self.I64 != 0 {
-            l += 2;
-            let mut x = self.I64 as u64;
+                if self.F32 != 0.0 {
+                    l += 5;
+                }
 
-            if self.I64 < 0 {
-                x = !x + 1;
-            }
+                if self.F64 != 0.0 {
+                    l += 9;
+                }
 
-            l += 2;
+                if !self.T.is_zero() {
+                    if (0..1 << 32).contains(&self.T.seconds) {
+                        l += 9;
+                    } else {
+                        l += 13;
+                    }
+                }
 
-            for _ in 0..8 {
-                if x <= 0x80 {
-                    break;
+                {
+                    let x = self.S.len();
+                    if x > 0 {
+                        if x > COLFER_SIZE_MAX {
+                            return Err(ColferError::MaxSizeBreach {
+                                field: "self::S",
+                                overflow: x - COLFER_SIZE_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x) + x;
+                    }
                 }
 
-                x >>= 7;
-                l += 1;
-            }
-        }
+                {
+                    let x = self.A.len();
+                    if x > 0 {
+                        if x > COLFER_SIZE_MAX {
+                            return Err(ColferError::MaxSizeBreach {
+                                field: "self::A",
+                                overflow: x - COLFER_SIZE_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x) + x;
+                    }
+                }
 
-        if self.F32 != 0.0 {
-            l += 5;
-        }
+                if let Some(ref v) = self.O {
+                    l += v.size()? + 1;
+                }
 
-        if self.F64 != 0.0 {
-            l += 9;
-        }
+                {
+                    let x = self.Os.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::Os",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
 
-        if self.T != UNIX_EPOCH {
-            let dur = self.T.duration_since(UNIX_EPOCH).unwrap();
-            if (dur.as_secs() as u64) < 1 << 32 {
-                l += 9;
-            } else {
-                l += 13;
-            }
-        }
+                        l += 1 + varint::size_usize(x);
+                        match os_sizes {
+                            Some(sizes) => l += sizes.iter().sum::<usize>(),
+                            None => l += self.Os.size()?,
+                        }
 
-        {
-            let mut x = self.S.len();
-            if x > 0 {
-                if x > COLFER_SIZE_MAX {
-                    return Err(ColferError::MaxSizeBreach {
-                        field: "self::S",
-                        overflow: x - COLFER_SIZE_MAX,
-                    });
-                }
-                l += x + 2;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
+                        if l > COLFER_SIZE_MAX {
+                            return Err(ColferError::MaxSizeBreach {
+                                field: "self::Os",
+                                overflow: l - COLFER_SIZE_MAX,
+                            });
+                        }
+                    }
                 }
-            }
-        }
 
-        if let Some(ref v) = self.O {
-            l += v.colf_marshal_len()? + 1;
-        }
+                {
+                    let x = self.Ss.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::Ss",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
 
-        {
-            let mut x = self.Os.len();
-            if x > 0 {
-                if x > COLFER_LIST_MAX {
-                    return Err(ColferError::MaxListBreach {
-                        field: "self::Os",
-                        overflow: x - COLFER_LIST_MAX,
-                    });
-                }
+                        l += 1 + varint::size_usize(x);
+                        for a in self.Ss.iter() {
+                            let xs = a.len();
+                            if xs > COLFER_SIZE_MAX {
+                                return Err(ColferError::MaxSizeBreach {
+                                    field: "self::Ss",
+                                    overflow: xs - COLFER_SIZE_MAX,
+                                });
+                            }
+                            l += varint::size_usize(xs) + xs;
+                        }
 
-                l += 2;
-                while x >= 0x80 {
-                    x >>= 7;
+                        if l > COLFER_SIZE_MAX {
+                            return Err(ColferError::MaxSizeBreach {
+                                field: "self::Ss",
+                                overflow: l - COLFER_SIZE_MAX,
+                            });
+                        }
+                    }
                 }
 
-                for vo in self.Os.iter() {
-                    if let Some(ref v) = vo {
-                        l += v.colf_marshal_len()?;
-                    } else {
-                        l += 1;
+                {
+                    let x = self.As.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::As",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
+
+                        l += 1 + varint::size_usize(x);
+                        for a in self.As.iter() {
+                            let xs = a.len();
+                            if xs > COLFER_SIZE_MAX {
+                                return Err(ColferError::MaxSizeBreach {
+                                    field: "self::As",
+                                    overflow: xs - COLFER_SIZE_MAX,
+                                });
+                            }
+                            l += varint::size_usize(xs) + xs;
+                        }
+
+                        if l > COLFER_SIZE_MAX {
+                            return Err(ColferError::MaxSizeBreach {
+                                field: "self::As",
+                                overflow: l - COLFER_SIZE_MAX,
+                            });
+                        }
                     }
                 }
 
-                if l > COLFER_SIZE_MAX {
-                    return Err(ColferError::MaxSizeBreach {
-                        field: "self::Os",
-                        overflow: x - COLFER_SIZE_MAX,
-                    });
+                if self.U8 > 0 {
+                    l += 2;
                 }
-            }
-        }
 
-        {
-            let mut x = self.Ss.len();
-            if x > 0 {
-                if x > COLFER_LIST_MAX {
-                    return Err(ColferError::MaxListBreach {
-                        field: "self::Ss",
-                        overflow: x - COLFER_LIST_MAX,
-                    });
+                if self.U16 >= 1 << 8 {
+                    l += 3;
+                } else if self.U16 != 0 {
+                    l += 2;
+                }
+
+                {
+                    let x = self.F32s.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::F32s",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x) + x * 4;
+                    }
                 }
 
-                l += 2;
-                while x >= 0x80 {
-                    x >>= 7;
+                {
+                    let x = self.F64s.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::F64s",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x) + x * 8;
+                    }
                 }
 
-                for a in self.Ss.iter() {
-                    let mut xs = a.len();
-                    if xs > COLFER_SIZE_MAX {
-                        return Err(ColferError::MaxSizeBreach {
-                            field: "self::Ss",
-                            overflow: xs - COLFER_SIZE_MAX,
-                        });
+                {
+                    let x = self.I32s.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::I32s",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x);
+                        for &v in self.I32s.iter() {
+                            l += varint::size_u32(varint::zigzag_i32(v));
+                        }
                     }
+                }
 
-                    l += xs + 1;
-                    while xs >= 0x80 {
-                        xs >>= 7;
-                        l += 1;
+                {
+                    let x = self.I64s.len();
+                    if x > 0 {
+                        if x > COLFER_LIST_MAX {
+                            return Err(ColferError::MaxListBreach {
+                                field: "self::I64s",
+                                overflow: x - COLFER_LIST_MAX,
+                            });
+                        }
+                        l += 1 + varint::size_usize(x);
+                        for &v in self.I64s.iter() {
+                            l += varint::size_u64(varint::zigzag_i64(v));
+                        }
                     }
                 }
 
-                if l >= COLFER_SIZE_MAX {
+                if l > COLFER_SIZE_MAX {
                     return Err(ColferError::MaxSizeBreach {
-                        field: "self::Ss",
+                        field: "self",
                         overflow: l - COLFER_SIZE_MAX,
                     });
                 }
+
+                Ok(l)
             }
-        }
 
-        {
-            let mut x = self.As.len();
-            if x > 0 {
-                if x > COLFER_LIST_MAX {
-                    return Err(ColferError::MaxListBreach {
-                        field: "self::As",
-                        overflow: x - COLFER_LIST_MAX,
-                    });
+            // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+            // nested messages then get decoded into rather than replaced.
+            fn unmarshal(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+                let len = data.len();
+                if len == 0 {
+                    return Err(ColferError::UnexpectedEof);
                 }
-
-                l += 2;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
+                let mut buf = Cursor::new(data);
+                let canonical = opts.validate_canonical;
+                let mut last_index = None;
+                self.unknown_fields = &[];
+                let (mut has_o, mut has_os) = (false, false);
+                if reuse {
+                    self.reset_values();
                 }
 
-                for a in self.As.iter() {
-                    let mut xs = a.len();
-                    if xs > COLFER_SIZE_MAX {
-                        return Err(ColferError::MaxSizeBreach {
-                            field: "self::As",
-                            overflow: xs - COLFER_SIZE_MAX,
-                        });
+                loop {
+                    buf_guard!(buf);
+                    let header = buf.get_u8();
+                    let byte = buf.position() - 1;
+                    if canonical && header != 0x7F {
+                        let index = header & 0x7F;
+                        match last_index {
+                            Some(last) if index == last => {
+                                return Err(ColferError::DuplicateField { byte });
+                            }
+                            Some(last) if index < last => {
+                                return Err(ColferError::HeaderOrder { byte });
+                            }
+                            _ => last_index = Some(index),
+                        }
+                    }
+                    if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                        if let Some(field) = COLFER_TYPES.field(header & 0x7F) {
+                            skip_field(field, header & 0x80 != 0, &mut buf)?;
+                            continue;
+                        }
                     }
 
-                    l += xs + 1;
-                    while xs >= 0x80 {
-                        xs >>= 7;
-                        l += 1;
+                    match header {
+                        0 => {
+                            self.B = true;
+                        }
+                        1 => {
+                            let x = get_varint32(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            canonical_guard!(canonical, x < 1 << 21, NonCanonicalForm, byte);
+                            self.U32 = x;
+                        }
+                        129 => {
+                            // 1 | 0x80
+                            buf_guard!(buf, 4);
+                            let x = buf.get_u32();
+                            canonical_guard!(canonical, x >= 1 << 21, NonCanonicalForm, byte);
+                            self.U32 = x;
+                        }
+                        2 => {
+                            let x = get_varint64(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            canonical_guard!(canonical, x < 1 << 49, NonCanonicalForm, byte);
+                            self.U64 = x;
+                        }
+                        130 => {
+                            // 2 | 0x80
+                            buf_guard!(buf, 8);
+                            let x = buf.get_u64();
+                            canonical_guard!(canonical, x >= 1 << 49, NonCanonicalForm, byte);
+                            self.U64 = x;
+                        }
+                        3 => {
+                            let x = get_varint32(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            // Negative values take the 3 | 0x80 header.
+                            canonical_guard!(canonical, x <= i32::MAX as u32, NonCanonicalForm, byte);
+                            self.I32 = x as i32;
+                        }
+                        131 => {
+                            // 3 | 0x80
+                            let x = get_varint32(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            canonical_guard!(canonical, x <= 1 << 31, NonCanonicalForm, byte);
+                            self.I32 = (!x).wrapping_add(1) as i32;
+                        }
+                        4 => {
+                            let x = get_varint64(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            canonical_guard!(canonical, x <= i64::MAX as u64, NonCanonicalForm, byte);
+                            self.I64 = x as i64;
+                        }
+                        132 => {
+                            // 4 | 0x80
+                            let x = get_varint64(&mut buf, canonical)?;
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            canonical_guard!(canonical, x <= 1 << 63, NonCanonicalForm, byte);
+                            self.I64 = (!x).wrapping_add(1) as i64;
+                        }
+                        5 => {
+                            buf_guard!(buf, 4);
+                            let x = f32::from_bits(buf.get_u32());
+                            canonical_guard!(canonical, x != 0.0, ZeroValue, byte);
+                            self.F32 = x;
+                        }
+                        6 => {
+                            buf_guard!(buf, 8);
+                            let x = f64::from_bits(buf.get_u64());
+                            canonical_guard!(canonical, x != 0.0, ZeroValue, byte);
+                            self.F64 = x;
+                        }
+                        7 => {
+                            self.T = get_timestamp(&mut buf, false, canonical)?;
+                        }
+                        135 => {
+                            // 7 | 0x80
+                            self.T = get_timestamp(&mut buf, true, canonical)?;
+                        }
+                        8 => {
+                            let x = get_text(&mut buf, "self::S", canonical)?;
+                            canonical_guard!(canonical, !x.is_empty(), ZeroValue, byte);
+                            self.S.set(x, "self::S")?;
+                        }
+                        9 => {
+                            let x = get_binary(&mut buf, "self::A", canonical)?;
+                            canonical_guard!(canonical, !x.is_empty(), ZeroValue, byte);
+                            self.A.set(x, "self::A")?;
+                        }
+                        10 => {
+                            has_o = true;
+                            let start = buf.position();
+                            let n = self.Os.decode_one(&mut self.O, &data[start..], &opts.nested()).map_err(|e| e.offset(start))?;
+                            buf.set_position(start + n);
+                        }
+                        11 => {
+                            let l = self.Os.read_len(&mut buf, "self::Os", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            has_os = true;
+                            let start = buf.position();
+                            let n = self.Os.decode(&data[start..], l, &opts.nested()).map_err(|e| e.offset(start))?;
+                            buf.set_position(start + n);
+                        }
+                        12 => {
+                            let l = self.Ss.read_len(&mut buf, "self::Ss", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            self.Ss.refill(l);
+                            let start = buf.position();
+                            let n = store::split_texts(data, start, l, "self::Ss", canonical, &mut self.Ss)?;
+                            buf.advance(n);
+                        }
+                        13 => {
+                            let l = self.As.read_len(&mut buf, "self::As", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            self.As.refill(l);
+                            let start = buf.position();
+                            let n = store::split_binaries(data, start, l, "self::As", canonical, &mut self.As)?;
+                            buf.advance(n);
+                        }
+                        14 => {
+                            buf_guard!(buf);
+                            let x = buf.get_u8();
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            self.U8 = x;
+                        }
+                        15 => {
+                            buf_guard!(buf, 2);
+                            let x = buf.get_u16();
+                            canonical_guard!(canonical, x >= 1 << 8, NonCanonicalForm, byte);
+                            self.U16 = x;
+                        }
+                        143 => {
+                            // 15 | 0x80
+                            buf_guard!(buf);
+                            let x = buf.get_u8();
+                            canonical_guard!(canonical, x != 0, ZeroValue, byte);
+                            self.U16 = u16::from(x);
+                        }
+                        16 => {
+                            let l = self.F32s.read_len(&mut buf, "self::F32s", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            buf_guard!(buf, l * 4);
+                            self.F32s.refill(l);
+                            let start = buf.position();
+                            self.F32s.add_floats(&data[start..start + l * 4]);
+                            buf.advance(l * 4);
+                        }
+                        17 => {
+                            let l = self.F64s.read_len(&mut buf, "self::F64s", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            buf_guard!(buf, l * 8);
+                            self.F64s.refill(l);
+                            let start = buf.position();
+                            self.F64s.add_floats(&data[start..start + l * 8]);
+                            buf.advance(l * 8);
+                        }
+                        18 => {
+                            let l = self.I32s.read_len(&mut buf, "self::I32s", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            self.I32s.refill(l);
+                            for _ in 0..l {
+                                self.I32s.add(varint::unzigzag_i32(get_varint32(&mut buf, canonical)?));
+                            }
+                        }
+                        19 => {
+                            let l = self.I64s.read_len(&mut buf, "self::I64s", canonical)?;
+                            canonical_guard!(canonical, l != 0, ZeroValue, byte);
+                            self.I64s.refill(l);
+                            for _ in 0..l {
+                                self.I64s.add(varint::unzigzag_i64(get_varint64(&mut buf, canonical)?));
+                            }
+                        }
+                        0x7F => {
+                            self.drop_unseen(reuse, has_o, has_os);
+                            return Ok(buf.position());
+                        }
+                        _ => {
+                            // Nothing tells how long unknown fields are, so they can only be kept
+                            // when the data is known to end with the terminator of this message.
+                            // Headers of known fields with a flag they don't take stay invalid.
+                            let unknown = usize::from(header & 0x7F) >= COLFER_TYPES.fields.len();
+                            if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                                self.unknown_fields = &data[byte..len - 1];
+                                self.drop_unseen(reuse, has_o, has_os);
+                                return Ok(len);
+                            }
+                            return Err(ColferError::UnknownHeader { byte });
+                        }
                     }
                 }
+            }
 
-                if l >= COLFER_SIZE_MAX {
-                    return Err(ColferError::MaxSizeBreach {
-                        field: "self::Ss",
-                        overflow: l - COLFER_SIZE_MAX,
-                    });
+            // Clears the nested messages kept for reuse that the data had no value for.
+            fn drop_unseen(&mut self, reuse: bool, has_o: bool, has_os: bool) {
+                if reuse && !has_o {
+                    self.O = None;
+                }
+                if reuse && !has_os {
+                    self.Os.clear();
                 }
             }
         }
+    };
+}
 
-        if self.U8 > 0 {
-            l += 2;
-        }
+#[cfg(feature = "alloc")]
+colfer_types_wire!([] ColferTypes<'a>);
 
-        if self.U16 >= 1 << 8 {
-            l += 3;
-        } else if self.U16 != 0 {
-            l += 2;
-        }
+// Nested data structure, as held by a field.
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+trait Nested {
+    fn put<B: BufMut>(&self, buf: &mut B);
+    fn size(&self) -> ColferResult<usize>;
+}
 
-        {
-            let mut x = self.F32s.len();
-            if x > 0 {
-                if x > COLFER_LIST_MAX {
-                    return Err(ColferError::MaxListBreach {
-                        field: "self::F32s",
-                        overflow: x - COLFER_LIST_MAX,
-                    });
-                }
+// List of nested data structures, as held by a field. It also decodes the single nested
+// field, which is allocated like the elements.
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+trait NestedList<'a> {
+    type Single: Nested;
+    fn put<B: BufMut>(&self, buf: &mut B);
+    fn size(&self) -> ColferResult<usize>;
+    // Decodes l elements from the start of data and returns the number of bytes read.
+    fn decode(&mut self, data: &'a [u8], l: usize, opts: &DecodeOptions) -> ColferResult<usize>;
+    // Decodes the start of data into slot and returns the number of bytes read.
+    fn decode_one(&self, slot: &mut Option<Self::Single>, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize>;
+}
 
-                l += 2 + x * 4;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
+#[cfg(feature = "alloc")]
+impl<'a> Nested for Box<ColferTypes<'a>> {
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        self.colf_marshal_to(buf);
+    }
+
+    fn size(&self) -> ColferResult<usize> {
+        self.colf_marshal_len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> NestedList<'a> for Vec<Option<ColferTypes<'a>>> {
+    type Single = Box<ColferTypes<'a>>;
+
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        for vio in self.iter() {
+            match vio {
+                Some(ref vi) => {
+                    vi.colf_marshal_to(buf);
                 }
+                None => buf.put_u8(0x7F),
             }
         }
+    }
 
-        {
-            let mut x = self.F64s.len();
-            if x > 0 {
-                if x > COLFER_LIST_MAX {
-                    return Err(ColferError::MaxListBreach {
-                        field: "self::F32s",
-                        overflow: x - COLFER_LIST_MAX,
-                    });
-                }
+    fn size(&self) -> ColferResult<usize> {
+        let mut l = 0;
+        for vo in self.iter() {
+            if let Some(ref v) = vo {
+                l += v.colf_marshal_len()?;
+            } else {
+                l += 1;
+            }
+        }
+        Ok(l)
+    }
 
-                l += 2 + x * 8;
-                while x >= 0x80 {
-                    x >>= 7;
-                    l += 1;
-                }
+    fn decode(&mut self, data: &'a [u8], l: usize, opts: &DecodeOptions) -> ColferResult<usize> {
+        // Elements already in the list are decoded into, to keep their allocations.
+        self.truncate(l);
+        self.reserve(l - self.len());
+        let mut n = 0;
+        for i in 0..l {
+            if i == self.len() {
+                self.push(None);
             }
+            let obj = self[i].get_or_insert_with(ColferTypes::default);
+            n += obj.unmarshal(&data[n..], opts, true).map_err(|e| e.offset(n))?;
         }
+        Ok(n)
+    }
 
-        if l > COLFER_SIZE_MAX {
-            return Err(ColferError::MaxSizeBreach {
-                field: "self",
-                overflow: l - COLFER_SIZE_MAX,
-            });
+    fn decode_one(&self, slot: &mut Option<Self::Single>, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        slot.get_or_insert_with(Box::default).unmarshal(data, opts, true)
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `ColferTypes`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct ColferTypesView<'a>(ColferView<'a, 20>);
+
+#[allow(non_snake_case)]
+impl<'a> ColferTypesView<'a> {
+    /// Indexes the `ColferTypes` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&COLFER_TYPES, data).map(ColferTypesView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn B(&self) -> bool {
+        self.0.get_bool(0)
+    }
+
+    pub fn U32(&self) -> ColferResult<u32> {
+        self.0.get_u32(1)
+    }
+
+    pub fn U64(&self) -> ColferResult<u64> {
+        self.0.get_u64(2)
+    }
+
+    pub fn I32(&self) -> ColferResult<i32> {
+        self.0.get_i32(3)
+    }
+
+    pub fn I64(&self) -> ColferResult<i64> {
+        self.0.get_i64(4)
+    }
+
+    pub fn F32(&self) -> ColferResult<f32> {
+        self.0.get_f32(5)
+    }
+
+    pub fn F64(&self) -> ColferResult<f64> {
+        self.0.get_f64(6)
+    }
+
+    pub fn T(&self) -> ColferResult<Timestamp> {
+        self.0.get_timestamp(7)
+    }
+
+    pub fn S(&self) -> ColferResult<&'a str> {
+        self.0.get_text(8)
+    }
+
+    pub fn A(&self) -> ColferResult<&'a [u8]> {
+        self.0.get_binary(9)
+    }
+
+    pub fn O(&self) -> ColferResult<Option<ColferTypesView<'a>>> {
+        Ok(self.0.get_struct(10, &COLFER_TYPES)?.map(ColferTypesView))
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn Os(&self) -> ColferResult<Vec<ColferTypesView<'a>>> {
+        Ok(self.0.get_structs(11, &COLFER_TYPES)?.into_iter().map(ColferTypesView).collect())
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn Ss(&self) -> ColferResult<Vec<&'a str>> {
+        self.0.get_texts(12)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn As(&self) -> ColferResult<Vec<&'a [u8]>> {
+        self.0.get_binaries(13)
+    }
+
+    pub fn U8(&self) -> ColferResult<u8> {
+        self.0.get_u8(14)
+    }
+
+    pub fn U16(&self) -> ColferResult<u16> {
+        self.0.get_u16(15)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn F32s(&self) -> ColferResult<Vec<f32>> {
+        self.0.get_f32s(16)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn F64s(&self) -> ColferResult<Vec<f64>> {
+        self.0.get_f64s(17)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn I32s(&self) -> ColferResult<Vec<i32>> {
+        self.0.get_i32s(18)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn I64s(&self) -> ColferResult<Vec<i64>> {
+        self.0.get_i64s(19)
+    }
+}
+
+/// `ColferTypes` decoded into an arena. Lists and nested data structures are allocated
+/// from the `Bump` given to `new_in`, and texts and binaries borrow from the input, so that
+/// a whole tree decodes with a handful of allocations, all freed at once with the arena.
+#[cfg(feature = "arena")]
+#[allow(non_snake_case)]
+#[derive(Debug)]
+pub struct ColferTypesArena<'a> {
+    /// B tests booleans.
+    pub B: bool,
+    /// U32 tests unsigned 32-bit integers.
+    pub U32: u32,
+    /// U64 tests unsigned 64-bit integers.
+    pub U64: u64,
+    /// I32 tests signed 32-bit integers.
+    pub I32: i32,
+    /// I64 tests signed 64-bit integers.
+    pub I64: i64,
+    /// F64 tests 64-bit floating points.
+    pub F64: f64,
+    /// F32 tests 32-bit floating points.
+    pub F32: f32,
+    /// T tests timestamps.
+    pub T: Timestamp,
+    /// S tests text.
+    pub S: &'a str,
+    /// A tests binaries.
+    pub A: &'a [u8],
+    /// O tests nested data structures.
+    pub O: Option<BumpBox<'a, ColferTypesArena<'a>>>,
+    /// Os tests data structure lists.
+    pub Os: BumpVec<'a, ColferTypesArena<'a>>,
+    /// Ss tests text lists.
+    pub Ss: BumpVec<'a, &'a str>,
+    /// As tests binary lists.
+    pub As: BumpVec<'a, &'a [u8]>,
+    /// U8 tests unsigned 8-bit integers.
+    pub U8: u8,
+    /// U16 tests unsigned 16-bit integers.
+    pub U16: u16,
+    /// F32s tests 32-bit floating point lists.
+    pub F32s: BumpVec<'a, f32>,
+    /// F64s tests 64-bit floating point lists.
+    pub F64s: BumpVec<'a, f64>,
+    /// I32s tests signed 32-bit integer lists.
+    pub I32s: BumpVec<'a, i32>,
+    /// I64s tests signed 64-bit integer lists.
+    pub I64s: BumpVec<'a, i64>,
+    /// Fields unknown to this version of the schema, as in `ColferTypes`.
+    pub unknown_fields: &'a [u8],
+}
+
+#[cfg(feature = "arena")]
+impl<'a> ColferTypesArena<'a> {
+    /// Returns an empty message that allocates from `arena`. Nothing is allocated until
+    /// it's decoded into.
+    pub fn new_in(arena: &'a Bump) -> Self {
+        ColferTypesArena {
+            B: bool::default(),
+            U32: u32::default(),
+            U64: u64::default(),
+            I32: i32::default(),
+            I64: i64::default(),
+            F64: f64::default(),
+            F32: f32::default(),
+            T: Timestamp::UNIX_EPOCH,
+            S: "",
+            A: &[],
+            O: None,
+            Os: BumpVec::new_in(arena),
+            Ss: BumpVec::new_in(arena),
+            As: BumpVec::new_in(arena),
+            U8: u8::default(),
+            U16: u16::default(),
+            F32s: BumpVec::new_in(arena),
+            F64s: BumpVec::new_in(arena),
+            I32s: BumpVec::new_in(arena),
+            I64s: BumpVec::new_in(arena),
+            unknown_fields: &[],
+        }
+    }
+}
+
+#[cfg(feature = "arena")]
+colfer_types_wire!([] ColferTypesArena<'a>);
+
+#[cfg(feature = "arena")]
+impl<'a> Nested for BumpBox<'a, ColferTypesArena<'a>> {
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        self.colf_marshal_to(buf);
+    }
+
+    fn size(&self) -> ColferResult<usize> {
+        self.colf_marshal_len()
+    }
+}
+
+#[cfg(feature = "arena")]
+impl<'a> NestedList<'a> for BumpVec<'a, ColferTypesArena<'a>> {
+    type Single = BumpBox<'a, ColferTypesArena<'a>>;
+
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        for v in self.iter() {
+            v.colf_marshal_to(buf);
         }
+    }
 
+    fn size(&self) -> ColferResult<usize> {
+        let mut l = 0;
+        for v in self.iter() {
+            l += v.colf_marshal_len()?;
+        }
         Ok(l)
     }
 
-    fn colf_unmarshal(&mut self, data: &'a [u8]) -> ColferResult<usize> {
-        let len = data.len();
-        if len == 0 {
-            return Err(ColferError::UnexpectedEof);
+    fn decode(&mut self, data: &'a [u8], l: usize, opts: &DecodeOptions) -> ColferResult<usize> {
+        // Elements already in the list are decoded into, to keep their allocations.
+        self.truncate(l);
+        self.reserve(l - self.len());
+        let mut n = 0;
+        for i in 0..l {
+            if i == self.len() {
+                let obj = ColferTypesArena::new_in(self.bump());
+                self.push(obj);
+            }
+            n += self[i].unmarshal(&data[n..], opts, true).map_err(|e| e.offset(n))?;
         }
-        let mut buf = ::std::io::Cursor::new(data);
-
-        loop {
-            let header = buf.get_u8();
-            buf_guard!(buf);
-            match header {
-                0 => {
-                    self.B = true;
-                }
-                1 => {
-                    let mut x = buf.get_u32_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u32;
-                            buf_guard!(buf);
+        Ok(n)
+    }
 
-                            if b < 0x80 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.U32 = x;
-                }
-                129 => {
-                    // 1 | 0x80
-                    self.U32 = buf.get_u32_be();
-                }
-                2 => {
-                    let mut x = buf.get_u64_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u64;
-                            buf_guard!(buf);
+    fn decode_one(&self, slot: &mut Option<Self::Single>, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        let arena = self.bump();
+        slot.get_or_insert_with(|| BumpBox::new_in(ColferTypesArena::new_in(arena), arena)).unmarshal(data, opts, true)
+    }
+}
 
-                            if b < 0x80 || shift == 56 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.U64 = x;
-                }
-                130 => {
-                    // 2 | 0x80
-                    self.U64 = buf.get_u64_be();
-                }
-                3 => {
-                    let mut x = buf.get_u32_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u32;
-                            buf_guard!(buf);
+#[cfg(feature = "parallel")]
+impl<'a> ColferTypes<'a> {
+    /// Same as `colf_marshal_binary`, with the elements of `Os` sized and encoded in
+    /// parallel, each into its own slice of the output. The result is byte-identical.
+    pub fn colf_marshal_binary_par(&self) -> ColferResult<Vec<u8>> {
+        let sizes = self
+            .Os
+            .par_iter()
+            .map(|vo| match vo {
+                Some(v) => v.colf_marshal_len(),
+                None => Ok(1),
+            })
+            .collect::<ColferResult<Vec<usize>>>()?;
+        let l = self.marshal_len(Some(&sizes))?;
+
+        let mut data = alloc::vec![0; l];
+        let start = {
+            let mut buf = &mut data[..];
+            self.marshal_head(&mut buf);
+            l - buf.len()
+        };
+        let end = start + sizes.iter().sum::<usize>();
+
+        let mut chunks = Vec::with_capacity(sizes.len());
+        let mut rest = &mut data[start..end];
+        for &n in sizes.iter() {
+            let (chunk, r) = mem::take(&mut rest).split_at_mut(n);
+            chunks.push(chunk);
+            rest = r;
+        }
+        self.Os.par_iter().zip(chunks).for_each(|(vo, chunk)| match vo {
+            Some(v) => {
+                v.colf_marshal_to(&mut &mut chunk[..]);
+            }
+            None => chunk[0] = 0x7F,
+        });
 
-                            if b < 0x80 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.I32 = x as i32;
-                }
-                131 => {
-                    let mut x = buf.get_u32_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u32;
-                            buf_guard!(buf);
+        self.marshal_tail(&mut &mut data[end..]);
+        Ok(data)
+    }
+}
 
-                            if b < 0x80 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.I32 = (!x + 1) as i32;
-                }
-                4 => {
-                    let mut x = buf.get_u64_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u64;
-                            buf_guard!(buf);
+/// `ColferTypes` with fixed-capacity storage, for devices that can't allocate. Texts and
+/// binaries hold up to `TEXT_CAP` bytes and lists up to `LIST_CAP` elements; decoding
+/// fails with `ColferError::MaxSizeBreach` or `ColferError::MaxListBreach` beyond that.
+/// Nested data structures can't be stored inline, so they stay encoded as views into the
+/// input.
+#[cfg(feature = "heapless")]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone)]
+pub struct ColferTypesHeapless<'a, const TEXT_CAP: usize, const LIST_CAP: usize> {
+    /// B tests booleans.
+    pub B: bool,
+    /// U32 tests unsigned 32-bit integers.
+    pub U32: u32,
+    /// U64 tests unsigned 64-bit integers.
+    pub U64: u64,
+    /// I32 tests signed 32-bit integers.
+    pub I32: i32,
+    /// I64 tests signed 64-bit integers.
+    pub I64: i64,
+    /// F64 tests 64-bit floating points.
+    pub F64: f64,
+    /// F32 tests 32-bit floating points.
+    pub F32: f32,
+    /// T tests timestamps.
+    pub T: Timestamp,
+    /// S tests text.
+    pub S: heapless::String<TEXT_CAP>,
+    /// A tests binaries.
+    pub A: heapless::Vec<u8, TEXT_CAP>,
+    /// O tests nested data structures.
+    pub O: Option<ColferTypesView<'a>>,
+    /// Os tests data structure lists.
+    pub Os: heapless::Vec<ColferTypesView<'a>, LIST_CAP>,
+    /// Ss tests text lists.
+    pub Ss: heapless::Vec<heapless::String<TEXT_CAP>, LIST_CAP>,
+    /// As tests binary lists.
+    pub As: heapless::Vec<heapless::Vec<u8, TEXT_CAP>, LIST_CAP>,
+    /// U8 tests unsigned 8-bit integers.
+    pub U8: u8,
+    /// U16 tests unsigned 16-bit integers.
+    pub U16: u16,
+    /// F32s tests 32-bit floating point lists.
+    pub F32s: heapless::Vec<f32, LIST_CAP>,
+    /// F64s tests 64-bit floating point lists.
+    pub F64s: heapless::Vec<f64, LIST_CAP>,
+    /// I32s tests signed 32-bit integer lists.
+    pub I32s: heapless::Vec<i32, LIST_CAP>,
+    /// I64s tests signed 64-bit integer lists.
+    pub I64s: heapless::Vec<i64, LIST_CAP>,
+    /// Fields unknown to this version of the schema, as in `ColferTypes`.
+    pub unknown_fields: &'a [u8],
+}
 
-                            if b < 0x80 || shift == 56 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.I64 = x as i64;
-                }
-                132 => {
-                    let mut x = buf.get_u64_be();
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as u64;
-                            buf_guard!(buf);
+#[cfg(feature = "heapless")]
+impl<'a, const TEXT_CAP: usize, const LIST_CAP: usize> Default for ColferTypesHeapless<'a, TEXT_CAP, LIST_CAP> {
+    fn default() -> Self {
+        ColferTypesHeapless {
+            B: bool::default(),
+            U32: u32::default(),
+            U64: u64::default(),
+            I32: i32::default(),
+            I64: i64::default(),
+            F64: f64::default(),
+            F32: f32::default(),
+            T: Timestamp::UNIX_EPOCH,
+            S: heapless::String::new(),
+            A: heapless::Vec::new(),
+            O: None,
+            Os: heapless::Vec::new(),
+            Ss: heapless::Vec::new(),
+            As: heapless::Vec::new(),
+            U8: u8::default(),
+            U16: u16::default(),
+            F32s: heapless::Vec::new(),
+            F64s: heapless::Vec::new(),
+            I32s: heapless::Vec::new(),
+            I64s: heapless::Vec::new(),
+            unknown_fields: &[],
+        }
+    }
+}
 
-                            if b < 0x80 || shift == 56 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
-                    self.I64 = (!x + 1) as i64;
-                }
-                5 => {
-                    self.F32 = f32::from_bits(buf.get_u32_be());
-                }
-                6 => {
-                    self.F64 = f64::from_bits(buf.get_u64_be());
-                }
-                7 => {
-                    let dur = Duration::new(buf.get_u32_be() as u64, buf.get_u32_be());
-                    self.T = SystemTime::now() - dur;
-                }
-                135 => {
-                    let dur = Duration::new(buf.get_u64_be(), buf.get_u32_be());
-                    self.T = SystemTime::now() - dur;
-                }
-                8 => {
-                    let mut x = buf.get_u8() as usize;
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as usize;
-                            buf_guard!(buf);
+#[cfg(feature = "heapless")]
+colfer_types_wire!([const TEXT_CAP: usize, const LIST_CAP: usize] ColferTypesHeapless<'a, TEXT_CAP, LIST_CAP>);
 
-                            if b < 0x80 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
+#[cfg(feature = "heapless")]
+impl<'a> Nested for ColferTypesView<'a> {
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(self.as_bytes());
+    }
 
-                    if x > COLFER_SIZE_MAX as usize {
-                        return Err(ColferError::MaxSizeBreach {
-                            field: "",
-                            overflow: x - COLFER_LIST_MAX as usize,
-                        });
-                    }
-                    buf_guard!(buf);
+    fn size(&self) -> ColferResult<usize> {
+        Ok(self.as_bytes().len())
+    }
+}
 
-                    let start = buf.position() as usize;
-                    self.S = ::std::str::from_utf8(&buf.get_ref()[start..x]).unwrap();
-                    buf.set_position(x as u64 + 1);
-                }
-                9 => {
-                    let mut x = buf.get_u8() as usize;
-                    if x >= 0x80 {
-                        x &= 0x7F;
-                        let mut shift: u8 = 7;
-                        loop {
-                            let b = buf.get_u8() as usize;
-                            buf_guard!(buf);
+#[cfg(feature = "heapless")]
+impl<'a, const N: usize> NestedList<'a> for heapless::Vec<ColferTypesView<'a>, N> {
+    type Single = ColferTypesView<'a>;
 
-                            if b < 0x80 {
-                                x |= b << shift;
-                                break;
-                            }
-                            x |= (b & 0x7F) << shift;
-                            shift += 7;
-                        }
-                    }
+    fn put<B: BufMut>(&self, buf: &mut B) {
+        for v in self.iter() {
+            v.put(buf);
+        }
+    }
 
-                    if x > COLFER_SIZE_MAX as usize {
-                        return Err(ColferError::MaxSizeBreach {
-                            field: "",
-                            overflow: x - COLFER_LIST_MAX as usize,
-                        });
-                    }
-                    buf_guard!(buf);
+    fn size(&self) -> ColferResult<usize> {
+        Ok(self.iter().map(|v| v.as_bytes().len()).sum())
+    }
 
-                    let start = buf.position() as usize;
-                    let mut a = Vec::with_capacity(x - start);
-                    a.copy_from_slice(&buf.get_ref()[start..x]);
-                    self.A = a;
-                }
-                10 => {
-                    let mut obj = Self::default();
-                    let start = buf.position() as usize;
-                    let n = obj.colf_unmarshal(&buf.get_ref()[start..])?;
-                    self.O = Some(Box::new(obj));
-                    buf.set_position(start as u64 + n as u64);
-                    buf_guard!(buf);
-                }
-                11...17 => {
-                    unimplemented!();
-                }
-                _ => {}
-            }
+    fn decode(&mut self, data: &'a [u8], l: usize, _opts: &DecodeOptions) -> ColferResult<usize> {
+        // The list length is within capacity, so pushes can't fail.
+        self.clear();
+        let mut n = 0;
+        for _ in 0..l {
+            let v = ColferTypesView::new(&data[n..]).map_err(|e| e.offset(n))?;
+            n += v.as_bytes().len();
+            let _ = self.push(v);
+        }
+        Ok(n)
+    }
+
+    fn decode_one(&self, slot: &mut Option<Self::Single>, data: &'a [u8], _opts: &DecodeOptions) -> ColferResult<usize> {
+        let v = ColferTypesView::new(data)?;
+        *slot = Some(v);
+        Ok(v.as_bytes().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Message with U32 = 5, followed by field 25, unknown to ColferTypes.
+    const UNKNOWN: [u8; 6] = [0x01, 0x05, 0x19, 0x7F, 0x01, 0x7F];
+
+    #[test]
+    fn back_to_back() {
+        let a = ColferTypes {
+            U32: 1,
+            S: "first",
+            ..ColferTypes::default()
+        }
+        .colf_marshal_binary()
+        .unwrap();
+        let b = ColferTypes {
+            U32: 2,
+            ..ColferTypes::default()
         }
+        .colf_marshal_binary()
+        .unwrap();
+        let data = [&a[..], &b[..]].concat();
+        let opts = DecodeOptions {
+            unknown_fields: UnknownFields::Preserve,
+            ..DecodeOptions::default()
+        };
+
+        let mut o = ColferTypes::default();
+        let n = o.colf_unmarshal_with(&data, &opts).unwrap();
+        assert_eq!(n, a.len());
+        assert_eq!((o.U32, o.S), (1, "first"));
+        assert_eq!(o.colf_unmarshal_with(&data[n..], &opts).unwrap(), b.len());
+        assert_eq!(o.U32, 2);
+        assert!(o.unknown_fields.is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_need_whole_input() {
+        let data = [&UNKNOWN[..], &UNKNOWN[..]].concat();
+        let opts = DecodeOptions {
+            unknown_fields: UnknownFields::Preserve,
+            ..DecodeOptions::default()
+        };
+
+        // The end of the unknown field can't be told from the next message.
+        let mut o = ColferTypes::default();
+        assert!(matches!(o.colf_unmarshal_with(&data, &opts), Err(ColferError::UnknownHeader { byte: 2 })));
+
+        let mut o = ColferTypes::default();
+        assert_eq!(o.colf_unmarshal_binary_with(&UNKNOWN, &opts).unwrap(), UNKNOWN.len());
+        assert_eq!(o.U32, 5);
+        assert_eq!(o.unknown_fields, &UNKNOWN[2..5]);
+        assert_eq!(o.colf_marshal_binary().unwrap(), UNKNOWN);
+    }
+
+    #[test]
+    fn preserve_keeps_known_headers_strict() {
+        let opts = DecodeOptions {
+            unknown_fields: UnknownFields::Preserve,
+            ..DecodeOptions::default()
+        };
+
+        // B takes no flag, so its flagged header is invalid rather than unknown.
+        let data = [0x80, 0x01, 0x05, 0x7F];
+        let r = ColferTypes::default().colf_unmarshal_binary_with(&data, &opts);
+        assert!(matches!(r, Err(ColferError::UnknownHeader { byte: 0 })), "{:?}", r);
+    }
+
+    #[test]
+    fn nested_error_offsets() {
+        let opts = DecodeOptions {
+            validate_canonical: true,
+            ..DecodeOptions::default()
+        };
+
+        // O at byte 4 holds an explicit zero U32 at byte 5.
+        let data = [8, 2, b'a', b'b', 10, 1, 0, 0x7F, 0x7F];
+        let r = ColferTypes::default().colf_unmarshal_with(&data, &opts);
+        assert!(matches!(r, Err(ColferError::ZeroValue { byte: 5 })), "{:?}", r);
+
+        // The second element of Os holds an explicit zero U32 at byte 3.
+        let data = [11, 2, 0x7F, 1, 0, 0x7F, 0x7F];
+        let r = ColferTypes::default().colf_unmarshal_with(&data, &opts);
+        assert!(matches!(r, Err(ColferError::ZeroValue { byte: 3 })), "{:?}", r);
+    }
+
+    #[test]
+    fn descriptor_fields() {
+        use ColferType::*;
+
+        let desc = ColferTypes::DESCRIPTOR;
+        let want = [
+            ("B", Bool, false),
+            ("U32", Uint32, false),
+            ("U64", Uint64, false),
+            ("I32", Int32, false),
+            ("I64", Int64, false),
+            ("F32", Float32, false),
+            ("F64", Float64, false),
+            ("T", Timestamp, false),
+            ("S", Text, false),
+            ("A", Binary, false),
+            ("O", Struct(desc), false),
+            ("Os", Struct(desc), true),
+            ("Ss", Text, true),
+            ("As", Binary, true),
+            ("U8", Uint8, false),
+            ("U16", Uint16, false),
+            ("F32s", Float32, true),
+            ("F64s", Float64, true),
+            ("I32s", Int32, true),
+            ("I64s", Int64, true),
+        ];
+        assert_eq!(desc.name, "ColferTypes");
+        assert_eq!(desc.fields.len(), want.len());
+        for (i, &(name, ty, list)) in want.iter().enumerate() {
+            let f = desc.field(i as u8).unwrap();
+            assert_eq!((f.name, f.index, f.ty, f.list), (name, i as u8, ty, list));
+            assert!(f.doc.starts_with(name), "doc of {}: {:?}", name, f.doc);
+            assert_eq!(desc.field_by_name(name), Some(f));
+        }
+        assert_eq!(desc.field(want.len() as u8), None);
+        assert_eq!(desc.field(0x7F), None);
+        assert_eq!(desc.field_by_name("b"), None);
+
+        // The indices are the headers on the wire.
+        let o = ColferTypes {
+            U16: 1,
+            ..ColferTypes::default()
+        };
+        assert_eq!(o.colf_marshal_binary().unwrap()[0] & 0x7F, desc.field_by_name("U16").unwrap().index);
+        let o = ColferTypes {
+            I64s: alloc::vec![1],
+            ..ColferTypes::default()
+        };
+        assert_eq!(o.colf_marshal_binary().unwrap()[0], desc.field_by_name("I64s").unwrap().index);
+    }
+
+    #[test]
+    fn projection_skips_other_fields() {
+        use alloc::vec;
+
+        let o = ColferTypes {
+            B: true,
+            U32: 5,
+            S: "skipped",
+            A: vec![1; 300],
+            O: Some(Box::new(ColferTypes { U32: 6, ..ColferTypes::default() })),
+            Os: vec![None, Some(ColferTypes { S: "os", ..ColferTypes::default() })],
+            Ss: vec!["a", "b"],
+            As: vec![&[2][..]],
+            U16: 7,
+            F64s: vec![0.5],
+            I64s: vec![-1],
+            ..ColferTypes::default()
+        };
+        let data = o.colf_marshal_binary().unwrap();
+        let mut got = ColferTypes::default();
+        assert_eq!(got.colf_unmarshal_fields(&data, &[1, 12, 19, 200]).unwrap(), data.len());
+        assert_eq!((got.U32, &got.Ss[..], &got.I64s[..]), (5, &["a", "b"][..], &[-1][..]));
+        assert!(!got.B && got.S.is_empty() && got.A.is_empty() && got.O.is_none());
+        assert!(got.Os.is_empty() && got.As.is_empty() && got.F64s.is_empty());
+        assert_eq!(got.U16, 0);
+
+        // Skipped payloads are not checked, so invalid UTF-8 passes when S is left out.
+        let data = [1, 5, 8, 2, 0xC3, 0x28, 14, 3, 0x7F];
+        let mut got = ColferTypes::default();
+        assert_eq!(got.colf_unmarshal_fields(&data, &[1, 14]).unwrap(), data.len());
+        assert_eq!((got.U32, got.S, got.U8), (5, "", 3));
+        assert!(ColferTypes::default().colf_unmarshal(&data).is_err());
+    }
+
+    #[test]
+    fn marshal_into_slice() {
+        use alloc::vec;
+
+        let o = ColferTypes {
+            U64: 1 << 40,
+            S: "text",
+            O: Some(Box::new(ColferTypes { U8: 3, ..ColferTypes::default() })),
+            F32s: vec![0.5; 3],
+            ..ColferTypes::default()
+        };
+        let want = o.colf_marshal_binary().unwrap();
+
+        let mut out = vec![0xAA; want.len()];
+        assert_eq!(o.colf_marshal_into(&mut out).unwrap(), want.len());
+        assert_eq!(out, want);
+
+        // Room to spare is left as it was.
+        let mut out = vec![0xAA; want.len() + 2];
+        assert_eq!(o.colf_marshal_into(&mut out).unwrap(), want.len());
+        assert_eq!((&out[..want.len()], &out[want.len()..]), (&want[..], &[0xAA, 0xAA][..]));
+
+        let mut out = vec![0xAA; want.len() - 1];
+        let r = o.colf_marshal_into(&mut out);
+        assert!(matches!(r, Err(ColferError::BufferTooSmall { overflow: 1 })), "{:?}", r);
+        assert!(out.iter().all(|&b| b == 0xAA));
+        let r = o.colf_marshal_into(&mut []);
+        assert!(matches!(r, Err(ColferError::BufferTooSmall { overflow }) if overflow == want.len()), "{:?}", r);
+    }
+
+    #[test]
+    fn unmarshal_into_reuses() {
+        use alloc::vec;
+
+        let full = ColferTypes {
+            U32: 5,
+            S: "first",
+            A: vec![1, 2, 3],
+            O: Some(Box::new(ColferTypes { U8: 3, ..ColferTypes::default() })),
+            Os: (0..4).map(|_| Some(ColferTypes { I32: -1, ..ColferTypes::default() })).collect(),
+            F32s: vec![0.5; 8],
+            I64s: vec![-1; 8],
+            ..ColferTypes::default()
+        }
+        .colf_marshal_binary()
+        .unwrap();
+        let mut o = ColferTypes::default();
+        assert_eq!(o.colf_unmarshal_into(&full).unwrap(), full.len());
+        let (a, os, f32s, i64s) = (o.A.as_ptr(), o.Os.as_ptr(), o.F32s.as_ptr(), o.I64s.as_ptr());
+
+        // Fields absent from the next message are cleared, lists keep their allocations.
+        let less = ColferTypes {
+            A: vec![4],
+            Os: vec![None],
+            F32s: vec![1.5],
+            ..ColferTypes::default()
+        }
+        .colf_marshal_binary()
+        .unwrap();
+        assert_eq!(o.colf_unmarshal_into(&less).unwrap(), less.len());
+        assert_eq!((o.U32, o.S, &o.A[..], &o.F32s[..]), (0, "", &[4][..], &[1.5][..]));
+        assert!(o.O.is_none() && o.I64s.is_empty());
+        // Elements decode as Some, the reused one cleared of its earlier I32.
+        assert!(o.Os.len() == 1 && o.Os[0].as_ref().unwrap().I32 == 0);
+        assert_eq!((o.A.as_ptr(), o.Os.as_ptr(), o.F32s.as_ptr()), (a, os, f32s));
+        assert!(o.F32s.capacity() >= 8 && o.I64s.capacity() >= 8);
+
+        // Back to the larger message without moving the lists.
+        assert_eq!(o.colf_unmarshal_into(&full).unwrap(), full.len());
+        assert_eq!((o.U32, o.S, o.Os.len(), o.O.as_ref().unwrap().U8), (5, "first", 4, 3));
+        assert_eq!((o.Os.as_ptr(), o.F32s.as_ptr(), o.I64s.as_ptr()), (os, f32s, i64s));
+        assert_eq!(o.colf_marshal_binary().unwrap(), full);
+
+        o.colf_reset();
+        assert!(o.O.is_none() && o.Os.is_empty() && o.F32s.is_empty() && o.A.is_empty());
+        assert!(o.Os.capacity() >= 4);
+    }
+
+    #[test]
+    fn int_lists_golden() {
+        use alloc::vec;
+
+        // Elements are zig-zag varints: 0, -1, 1, -2 and on encode as 0, 1, 2, 3.
+        let o = ColferTypes {
+            I32s: vec![i32::MIN, -1, 0, 1, i32::MAX],
+            I64s: vec![i64::MIN, -2, i64::MAX],
+            ..ColferTypes::default()
+        };
+        let want = [
+            18, 5, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 1, 0, 2, 0xFE, 0xFF, 0xFF, 0xFF, 0x0F, //
+            19, 3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 3, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //
+            0x7F,
+        ];
+        assert_eq!(o.colf_marshal_len().unwrap(), want.len());
+        assert_eq!(o.colf_marshal_binary().unwrap(), want);
+        let mut got = ColferTypes::default();
+        assert_eq!(got.colf_unmarshal_binary(&want).unwrap(), want.len());
+        assert_eq!((got.I32s, got.I64s), (o.I32s, o.I64s));
+    }
+
+    #[test]
+    fn int_lists_empty_and_long() {
+        use alloc::vec;
+
+        // Empty lists are left out, and an explicit one is only accepted when not canonical.
+        let o = ColferTypes {
+            I32s: vec![],
+            I64s: vec![],
+            ..ColferTypes::default()
+        };
+        assert_eq!(o.colf_marshal_binary().unwrap(), [0x7F]);
+        let mut got = ColferTypes::default();
+        assert_eq!(got.colf_unmarshal_binary(&[19, 0, 0x7F]).unwrap(), 3);
+        assert!(got.I64s.is_empty());
+        let opts = DecodeOptions {
+            validate_canonical: true,
+            ..DecodeOptions::default()
+        };
+        let r = ColferTypes::default().colf_unmarshal_with(&[19, 0, 0x7F], &opts);
+        assert!(matches!(r, Err(ColferError::ZeroValue { byte: 0 })), "{:?}", r);
+
+        // 200 elements take a two-byte length.
+        let o = ColferTypes {
+            I32s: vec![-1; 200],
+            ..ColferTypes::default()
+        };
+        let data = o.colf_marshal_binary().unwrap();
+        assert_eq!(&data[..3], &[18, 0xC8, 0x01]);
+        assert!(data[3..203].iter().all(|&b| b == 1));
+        assert_eq!(&data[203..], &[0x7F]);
+        let mut got = ColferTypes::default();
+        assert_eq!(got.colf_unmarshal_binary(&data).unwrap(), data.len());
+        assert_eq!(got.I32s, o.I32s);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn marshal_par_is_byte_identical() {
+        use alloc::vec;
+
+        let inner = || ColferTypes {
+            S: "inner",
+            I64: -7,
+            Ss: vec!["a", "bc"],
+            ..ColferTypes::default()
+        };
+        let mut o = ColferTypes {
+            B: true,
+            U32: 1 << 22,
+            S: "head",
+            O: Some(Box::new(inner())),
+            F64s: vec![1.5, -2.5],
+            I32s: vec![-1, 1 << 20],
+            ..ColferTypes::default()
+        };
+        for i in 0..1000u32 {
+            o.Os.push(match i % 3 {
+                0 => None,
+                1 => Some(ColferTypes { U32: i, ..ColferTypes::default() }),
+                _ => Some(inner()),
+            });
+        }
+
+        let data = o.colf_marshal_binary_par().unwrap();
+        assert_eq!(data, o.colf_marshal_binary().unwrap());
+        let mut back = ColferTypes::default();
+        assert_eq!(back.colf_unmarshal_binary(&data).unwrap(), data.len());
+        assert_eq!(back.Os.len(), 1000);
+
+        o.Os.clear();
+        assert_eq!(o.colf_marshal_binary_par().unwrap(), o.colf_marshal_binary().unwrap());
+    }
+
+    #[cfg(feature = "arena")]
+    fn sample<'a>() -> ColferTypes<'a> {
+        use alloc::vec;
+
+        ColferTypes {
+            U32: 1 << 22,
+            I32: -3,
+            S: "text",
+            A: vec![1, 2],
+            O: Some(Box::new(ColferTypes {
+                Ss: vec!["in", "o"],
+                O: Some(Box::new(ColferTypes { U8: 3, ..ColferTypes::default() })),
+                ..ColferTypes::default()
+            })),
+            Os: vec![None, Some(ColferTypes { S: "os", I64s: vec![-1, 1 << 40], ..ColferTypes::default() })],
+            Ss: vec!["a", "bcd"],
+            As: vec![&[4][..], &[]],
+            F32s: vec![0.5; 5],
+            F64s: vec![-0.25; 3],
+            I32s: vec![i32::MIN, 7],
+            ..ColferTypes::default()
+        }
+    }
+
+    // Whether x lies in memory allocated by arena.
+    #[cfg(feature = "arena")]
+    fn in_arena<T>(arena: &Bump, x: *const T) -> bool {
+        let x = x as usize;
+        unsafe { arena.iter_allocated_chunks_raw() }.any(|(p, n)| (p as usize..p as usize + n).contains(&x))
+    }
+
+    #[cfg(feature = "arena")]
+    #[test]
+    fn arena_round_trip() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let arena = Bump::new();
+        let mut o = ColferTypesArena::new_in(&arena);
+        assert_eq!(o.colf_unmarshal_binary(&data).unwrap(), data.len());
+        assert_eq!((o.U32, o.I32, o.S, o.A), (1 << 22, -3, "text", &[1, 2][..]));
+        assert_eq!((&o.Ss[..], &o.As[..]), (&["a", "bcd"][..], &[&[4][..], &[]][..]));
+        assert_eq!((&o.F32s[..], &o.F64s[..], &o.I32s[..]), (&[0.5; 5][..], &[-0.25; 3][..], &[i32::MIN, 7][..]));
+        assert_eq!(o.colf_marshal_len().unwrap(), data.len());
+        assert_eq!(o.colf_marshal_binary().unwrap(), data);
+
+        // Decoding again reuses what the first decode allocated.
+        let used = arena.allocated_bytes();
+        assert_eq!(o.colf_unmarshal_into(&data).unwrap(), data.len());
+        assert_eq!(arena.allocated_bytes(), used);
+        assert_eq!(o.colf_marshal_binary().unwrap(), data);
+    }
+
+    #[cfg(feature = "arena")]
+    #[test]
+    fn arena_holds_nested() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let arena = Bump::new();
+        let mut o = ColferTypesArena::new_in(&arena);
+        o.colf_unmarshal_binary(&data).unwrap();
+
+        let inner = o.O.as_ref().unwrap();
+        assert!(in_arena(&arena, &**inner));
+        assert!(in_arena(&arena, &**inner.O.as_ref().unwrap()));
+        assert!(in_arena(&arena, inner.Ss.as_ptr()));
+        assert_eq!(&inner.Ss[..], &["in", "o"][..]);
+        assert!(in_arena(&arena, o.Os.as_ptr()));
+        assert!(in_arena(&arena, o.Os[1].I64s.as_ptr()));
+        assert_eq!((o.Os.len(), o.Os[1].S, &o.Os[1].I64s[..]), (2, "os", &[-1, 1 << 40][..]));
+    }
+
+    #[cfg(feature = "arena")]
+    #[test]
+    fn arena_matches_alloc() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let mut want = ColferTypes::default();
+        want.colf_unmarshal_binary(&data).unwrap();
+        let arena = Bump::new();
+        let mut got = ColferTypesArena::new_in(&arena);
+        got.colf_unmarshal_binary(&data).unwrap();
+        assert_eq!(got.colf_marshal_binary().unwrap(), want.colf_marshal_binary().unwrap());
+        assert_eq!(got.O.as_ref().unwrap().O.as_ref().unwrap().U8, want.O.as_ref().unwrap().O.as_ref().unwrap().U8);
+
+        // The decode options apply as well, nested messages included.
+        let opts = DecodeOptions {
+            validate_canonical: true,
+            ..DecodeOptions::default()
+        };
+        let data = [8, 2, b'a', b'b', 10, 1, 0, 0x7F, 0x7F];
+        let r = ColferTypesArena::new_in(&arena).colf_unmarshal_with(&data, &opts);
+        assert!(matches!(r, Err(ColferError::ZeroValue { byte: 5 })), "{:?}", r);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn heapless_matches_alloc() {
+        use alloc::vec;
+
+        let o = ColferTypes {
+            U64: 1 << 50,
+            T: Timestamp { seconds: -1, nanos: 2 },
+            S: "text",
+            A: vec![1, 2],
+            O: Some(Box::new(ColferTypes { U8: 3, ..ColferTypes::default() })),
+            Os: vec![None, Some(ColferTypes { S: "os", ..ColferTypes::default() })],
+            Ss: vec!["a", "bcd"],
+            As: vec![&[4][..], &[]],
+            F32s: vec![0.5; 5],
+            F64s: vec![-0.25; 3],
+            I64s: vec![-1, 1 << 40],
+            ..ColferTypes::default()
+        };
+        let data = o.colf_marshal_binary().unwrap();
+
+        let mut h = ColferTypesHeapless::<4, 5>::default();
+        assert_eq!(h.colf_unmarshal_binary(&data).unwrap(), data.len());
+        assert_eq!((h.S.as_str(), &h.A[..], h.Ss.len(), &h.As[1][..]), ("text", &[1, 2][..], 2, &[][..]));
+        assert_eq!(h.O.unwrap().U8().unwrap(), 3);
+        assert_eq!(h.Os[1].S().unwrap(), "os");
+        assert_eq!((&h.F32s[..], &h.F64s[..], &h.I64s[..]), (&o.F32s[..], &o.F64s[..], &o.I64s[..]));
+        assert_eq!(h.colf_marshal_len().unwrap(), data.len());
+        assert_eq!(h.colf_marshal_binary().unwrap(), data);
+
+        let mut h = ColferTypesHeapless::<3, 5>::default();
+        let r = h.colf_unmarshal_binary(&data);
+        assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::S", overflow: 1 })), "{:?}", r);
+        let mut h = ColferTypesHeapless::<4, 4>::default();
+        let r = h.colf_unmarshal_binary(&data);
+        assert!(matches!(r, Err(ColferError::MaxListBreach { field: "self::F32s", overflow: 1 })), "{:?}", r);
+
+        let data = ColferTypes { Ss: vec!["ab", "abcde"], ..ColferTypes::default() }.colf_marshal_binary().unwrap();
+        let r = ColferTypesHeapless::<4, 4>::default().colf_unmarshal_binary(&data);
+        assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::Ss", overflow: 1 })), "{:?}", r);
     }
 }
@@ -0,0 +1,264 @@
+//! Variable-length integer encoding as used by Colfer: 7 bits per byte, least significant
+//! group first, with the 0x80 bit set on all bytes but the last. 64-bit values stop at 9
+//! bytes, the last of which carries a full 8 bits.
+
+use bytes::BufMut;
+
+use super::error::{ColferError, ColferResult};
+
+/// Maximum encoded size of a 32-bit value.
+pub const MAX_LEN_32: usize = 5;
+/// Maximum encoded size of a 64-bit value.
+pub const MAX_LEN_64: usize = 9;
+
+/// Returns the encoded size of `x`.
+#[inline]
+pub fn size_u32(x: u32) -> usize {
+    let bits = 32 - (x | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// Returns the encoded size of `x`.
+#[inline]
+pub fn size_u64(x: u64) -> usize {
+    let bits = 64 - (x | 1).leading_zeros() as usize;
+    if bits > 56 {
+        MAX_LEN_64
+    } else {
+        bits.div_ceil(7)
+    }
+}
+
+/// Returns the encoded size of `x`.
+#[inline]
+pub fn size_usize(x: usize) -> usize {
+    size_u64(x as u64)
+}
+
+/// Encodes `x` at the start of `out` and returns the number of bytes written.
+///
+/// Panics when `out` is shorter than `size_u32(x)`.
+#[inline]
+pub fn encode_u32(out: &mut [u8], x: u32) -> usize {
+    encode_u64(out, u64::from(x))
+}
+
+/// Encodes `x` at the start of `out` and returns the number of bytes written.
+///
+/// Panics when `out` is shorter than `size_u64(x)`.
+#[inline]
+pub fn encode_u64(out: &mut [u8], mut x: u64) -> usize {
+    if x < 1 << 7 {
+        out[0] = x as u8;
+        return 1;
+    }
+    if x < 1 << 14 {
+        out[0] = x as u8 | 0x80;
+        out[1] = (x >> 7) as u8;
+        return 2;
+    }
+
+    let mut i = 0;
+    while x >= 0x80 && i < MAX_LEN_64 - 1 {
+        out[i] = x as u8 | 0x80;
+        x >>= 7;
+        i += 1;
+    }
+    out[i] = x as u8;
+    i + 1
+}
+
+/// Encodes `x` at the start of `out` and returns the number of bytes written.
+///
+/// Panics when `out` is shorter than `size_usize(x)`.
+#[inline]
+pub fn encode_usize(out: &mut [u8], x: usize) -> usize {
+    encode_u64(out, x as u64)
+}
+
+/// Appends the encoding of `x` to `buf`.
+#[inline]
+pub fn put_u32<B: BufMut>(buf: &mut B, x: u32) {
+    put_u64(buf, u64::from(x))
+}
+
+/// Appends the encoding of `x` to `buf`.
+#[inline]
+pub fn put_u64<B: BufMut>(buf: &mut B, x: u64) {
+    if x < 1 << 7 {
+        buf.put_u8(x as u8);
+    } else {
+        let mut a = [0; MAX_LEN_64];
+        let n = encode_u64(&mut a, x);
+        buf.put_slice(&a[..n]);
+    }
+}
+
+/// Appends the encoding of `x` to `buf`.
+#[inline]
+pub fn put_usize<B: BufMut>(buf: &mut B, x: usize) {
+    put_u64(buf, x as u64)
+}
+
+/// Decodes the value at the start of `data` and returns it with the number of bytes
+/// read. Bits past 32 are dropped, so the read may be longer than `size_u32` of the
+/// value, which canonical decoding rejects.
+#[inline]
+pub fn decode_u32(data: &[u8]) -> ColferResult<(u32, usize)> {
+    match data {
+        [b0, ..] if *b0 < 0x80 => return Ok((u32::from(*b0), 1)),
+        [b0, b1, ..] if *b1 < 0x80 => return Ok((u32::from(*b0 & 0x7F) | u32::from(*b1) << 7, 2)),
+        _ => {}
+    }
+
+    let mut x: u32 = 0;
+    let mut shift: u32 = 0;
+    for (i, &b) in data.iter().enumerate() {
+        let b = u32::from(b);
+        if b < 0x80 {
+            x |= b.checked_shl(shift).unwrap_or(0);
+            return Ok((x, i + 1));
+        }
+        x |= (b & 0x7F).checked_shl(shift).unwrap_or(0);
+        shift += 7;
+    }
+    Err(ColferError::UnexpectedEof)
+}
+
+/// Decodes the value at the start of `data` and returns it with the number of bytes
+/// read.
+#[inline]
+pub fn decode_u64(data: &[u8]) -> ColferResult<(u64, usize)> {
+    match data {
+        [b0, ..] if *b0 < 0x80 => return Ok((u64::from(*b0), 1)),
+        [b0, b1, ..] if *b1 < 0x80 => return Ok((u64::from(*b0 & 0x7F) | u64::from(*b1) << 7, 2)),
+        _ => {}
+    }
+
+    let mut x: u64 = 0;
+    let mut shift: u32 = 0;
+    for (i, &b) in data.iter().enumerate() {
+        let b = u64::from(b);
+        if b < 0x80 || i == MAX_LEN_64 - 1 {
+            x |= b << shift;
+            return Ok((x, i + 1));
+        }
+        x |= (b & 0x7F) << shift;
+        shift += 7;
+    }
+    Err(ColferError::UnexpectedEof)
+}
+
+/// Decodes the value at the start of `data` and returns it with the number of bytes
+/// read.
+#[inline]
+pub fn decode_usize(data: &[u8]) -> ColferResult<(usize, usize)> {
+    decode_u64(data).map(|(x, n)| (x as usize, n))
+}
+
+/// Maps signed values onto unsigned ones with small magnitudes kept small: 0, -1, 1, -2
+/// and 2 become 0, 1, 2, 3 and 4. The elements of integer lists are encoded this way, as
+/// they have no header to carry the sign.
+#[inline]
+pub fn zigzag_i32(x: i32) -> u32 {
+    ((x << 1) ^ (x >> 31)) as u32
+}
+
+/// Inverse of `zigzag_i32`.
+#[inline]
+pub fn unzigzag_i32(x: u32) -> i32 {
+    (x >> 1) as i32 ^ -((x & 1) as i32)
+}
+
+/// Maps signed values onto unsigned ones like `zigzag_i32`.
+#[inline]
+pub fn zigzag_i64(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+/// Inverse of `zigzag_i64`.
+#[inline]
+pub fn unzigzag_i64(x: u64) -> i64 {
+    (x >> 1) as i64 ^ -((x & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Values at the size boundaries, with their encoded size.
+    const SIZES: [(u64, usize); 9] = [
+        (0, 1),
+        (127, 1),
+        (128, 2),
+        ((1 << 14) - 1, 2),
+        (1 << 14, 3),
+        (1 << 49, 8),
+        ((1 << 56) - 1, 8),
+        (1 << 56, 9),
+        (u64::MAX, 9),
+    ];
+
+    #[test]
+    fn size_and_round_trip() {
+        for &(x, size) in SIZES.iter() {
+            assert_eq!(size_u64(x), size, "size of {}", x);
+            let mut a = [0; MAX_LEN_64];
+            assert_eq!(encode_u64(&mut a, x), size, "encoding of {}", x);
+            assert_eq!(decode_u64(&a).unwrap(), (x, size), "decoding of {}", x);
+
+            let mut buf = [0; MAX_LEN_64];
+            let rest = {
+                let mut b = &mut buf[..];
+                put_u64(&mut b, x);
+                b.len()
+            };
+            assert_eq!(&buf[..MAX_LEN_64 - rest], &a[..size], "put of {}", x);
+
+            if x <= u64::from(u32::MAX) {
+                assert_eq!(size_u32(x as u32), size, "size of {}", x);
+                assert_eq!(decode_u32(&a).unwrap(), (x as u32, size), "decoding of {}", x);
+            }
+        }
+    }
+
+    #[test]
+    fn max_takes_nine_bytes() {
+        // The ninth byte has no continuation bit, so it carries all 8 of the top bits.
+        let mut a = [0; MAX_LEN_64];
+        assert_eq!(encode_u64(&mut a, u64::MAX), 9);
+        assert_eq!(a, [0xFF; 9]);
+        assert_eq!(decode_u64(&[0xFF; 12]).unwrap(), (u64::MAX, 9));
+
+        assert_eq!(encode_u64(&mut a, 1 << 63), 9);
+        assert_eq!(a, [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80]);
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(matches!(decode_u32(&[]), Err(ColferError::UnexpectedEof)));
+        assert!(matches!(decode_u64(&[]), Err(ColferError::UnexpectedEof)));
+        for &(x, size) in SIZES.iter() {
+            let mut a = [0; MAX_LEN_64];
+            encode_u64(&mut a, x);
+            for n in 0..size {
+                assert!(matches!(decode_u64(&a[..n]), Err(ColferError::UnexpectedEof)), "{} cut at {}", x, n);
+                assert!(matches!(decode_u32(&a[..n]), Err(ColferError::UnexpectedEof)), "{} cut at {}", x, n);
+            }
+        }
+    }
+
+    #[test]
+    fn zigzag_extremes() {
+        assert_eq!(zigzag_i32(i32::MAX), u32::MAX - 1);
+        assert_eq!(zigzag_i32(i32::MIN), u32::MAX);
+        assert_eq!(zigzag_i64(i64::MAX), u64::MAX - 1);
+        assert_eq!(zigzag_i64(i64::MIN), u64::MAX);
+        for &x in [0, -1, 1, i32::MIN, i32::MAX].iter() {
+            assert_eq!(unzigzag_i32(zigzag_i32(x)), x);
+        }
+        for &x in [0, -1, 1, i64::MIN, i64::MAX].iter() {
+            assert_eq!(unzigzag_i64(zigzag_i64(x)), x);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use super::bulk;
+#[cfg(feature = "alloc")]
+use super::{decode::{get_list_len, get_varint32, get_varint64},
+            varint};
+use super::{cursor::Cursor,
+            decode::{get_binary, get_f32, get_f64, get_i32, get_i64, get_text, get_timestamp, get_u16, get_u32, get_u64,
+                     get_u8, scan_message},
+            descriptor::MessageDescriptor,
+            error::ColferResult,
+            timestamp::Timestamp};
+
+/// Zero-copy random access to an encoded message with up to `N` fields. The headers are
+/// scanned once on construction to index where each field starts; values are only
+/// decoded when asked for, so fields nobody reads cost no more than skipping them.
+///
+/// The getters take the field index and must match the field type, which the generated
+/// `*View` types take care of. Absent fields read as their zero value.
+#[derive(Debug, Clone, Copy)]
+pub struct ColferView<'a, const N: usize> {
+    data: &'a [u8],
+    // Header offset plus one for each field index, zero when absent.
+    offsets: [u32; N],
+}
+
+impl<'a, const N: usize> ColferView<'a, N> {
+    /// Indexes the message described by `desc` at the start of `data`.
+    pub fn new(desc: &'static MessageDescriptor, data: &'a [u8]) -> ColferResult<Self> {
+        let mut offsets = [0; N];
+        let n = scan_message(desc, data, |field, byte| {
+            if let Some(offset) = offsets.get_mut(field.index as usize) {
+                *offset = byte as u32 + 1;
+            }
+        })?;
+
+        Ok(ColferView {
+            data: &data[..n],
+            offsets,
+        })
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Whether the message has a value for the field.
+    pub fn has(&self, index: u8) -> bool {
+        self.offsets.get(index as usize).is_some_and(|&o| o != 0)
+    }
+
+    // Positions a cursor on the payload of the field, with the 0x80 bit of its header.
+    fn field(&self, index: u8) -> Option<(Cursor<'a>, bool)> {
+        match self.offsets.get(index as usize) {
+            Some(&o) if o != 0 => {
+                let mut buf = Cursor::new(self.data);
+                buf.set_position(o as usize);
+                Some((buf, self.data[o as usize - 1] & 0x80 != 0))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, index: u8) -> bool {
+        self.has(index)
+    }
+
+    pub fn get_u8(&self, index: u8) -> ColferResult<u8> {
+        match self.field(index) {
+            Some((mut buf, _)) => get_u8(&mut buf),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_u16(&self, index: u8) -> ColferResult<u16> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_u16(&mut buf, flag),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_u32(&self, index: u8) -> ColferResult<u32> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_u32(&mut buf, flag),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_u64(&self, index: u8) -> ColferResult<u64> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_u64(&mut buf, flag),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_i32(&self, index: u8) -> ColferResult<i32> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_i32(&mut buf, flag),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_i64(&self, index: u8) -> ColferResult<i64> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_i64(&mut buf, flag),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_f32(&self, index: u8) -> ColferResult<f32> {
+        match self.field(index) {
+            Some((mut buf, _)) => get_f32(&mut buf),
+            None => Ok(0.0),
+        }
+    }
+
+    pub fn get_f64(&self, index: u8) -> ColferResult<f64> {
+        match self.field(index) {
+            Some((mut buf, _)) => get_f64(&mut buf),
+            None => Ok(0.0),
+        }
+    }
+
+    pub fn get_timestamp(&self, index: u8) -> ColferResult<Timestamp> {
+        match self.field(index) {
+            Some((mut buf, flag)) => get_timestamp(&mut buf, flag, false),
+            None => Ok(Timestamp::UNIX_EPOCH),
+        }
+    }
+
+    pub fn get_text(&self, index: u8) -> ColferResult<&'a str> {
+        match self.field(index) {
+            Some((mut buf, _)) => get_text(&mut buf, "", false),
+            None => Ok(""),
+        }
+    }
+
+    pub fn get_binary(&self, index: u8) -> ColferResult<&'a [u8]> {
+        match self.field(index) {
+            Some((mut buf, _)) => get_binary(&mut buf, "", false),
+            None => Ok(&[]),
+        }
+    }
+
+    /// Returns the nested data structure of the field, described by `desc`.
+    pub fn get_struct<const M: usize>(&self, index: u8, desc: &'static MessageDescriptor) -> ColferResult<Option<ColferView<'a, M>>> {
+        match self.field(index) {
+            Some((buf, _)) => {
+                let start = buf.position();
+                ColferView::new(desc, &self.data[start..]).map(Some).map_err(|e| e.offset(start))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+// The list getters collect into a `Vec`.
+#[cfg(feature = "alloc")]
+impl<'a, const N: usize> ColferView<'a, N> {
+    pub fn get_texts(&self, index: u8) -> ColferResult<Vec<&'a str>> {
+        let mut a = Vec::new();
+        if let Some((l, start)) = self.list(index)? {
+            a.reserve(l);
+            bulk::split_texts(self.data, start, l, "", false, &mut a)?;
+        }
+        Ok(a)
+    }
+
+    pub fn get_binaries(&self, index: u8) -> ColferResult<Vec<&'a [u8]>> {
+        let mut a = Vec::new();
+        if let Some((l, start)) = self.list(index)? {
+            a.reserve(l);
+            bulk::split_binaries(self.data, start, l, "", false, &mut a)?;
+        }
+        Ok(a)
+    }
+
+    pub fn get_f32s(&self, index: u8) -> ColferResult<Vec<f32>> {
+        let mut a = Vec::new();
+        if let Some((l, start)) = self.list(index)? {
+            bulk::extend_f32s(&mut a, &self.data[start..start + l * 4]);
+        }
+        Ok(a)
+    }
+
+    pub fn get_f64s(&self, index: u8) -> ColferResult<Vec<f64>> {
+        let mut a = Vec::new();
+        if let Some((l, start)) = self.list(index)? {
+            bulk::extend_f64s(&mut a, &self.data[start..start + l * 8]);
+        }
+        Ok(a)
+    }
+
+    pub fn get_i32s(&self, index: u8) -> ColferResult<Vec<i32>> {
+        self.get_list(index, |buf| get_varint32(buf, false).map(varint::unzigzag_i32))
+    }
+
+    pub fn get_i64s(&self, index: u8) -> ColferResult<Vec<i64>> {
+        self.get_list(index, |buf| get_varint64(buf, false).map(varint::unzigzag_i64))
+    }
+
+    /// Returns the list of nested data structures of the field, described by `desc`.
+    pub fn get_structs<const M: usize>(&self, index: u8, desc: &'static MessageDescriptor) -> ColferResult<Vec<ColferView<'a, M>>> {
+        self.get_list(index, |buf| {
+            let start = buf.position();
+            let view = ColferView::new(desc, &buf.get_ref()[start..]).map_err(|e| e.offset(start))?;
+            buf.set_position(start + view.data.len());
+            Ok(view)
+        })
+    }
+
+    // Returns the element count of the list field and the position of its first element.
+    // The scan already checked the elements are in bounds.
+    fn list(&self, index: u8) -> ColferResult<Option<(usize, usize)>> {
+        match self.field(index) {
+            Some((mut buf, _)) => {
+                let l = get_list_len(&mut buf, "", false)?;
+                Ok(Some((l, buf.position())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_list<T, F>(&self, index: u8, mut f: F) -> ColferResult<Vec<T>>
+    where
+        F: FnMut(&mut Cursor<'a>) -> ColferResult<T>,
+    {
+        match self.field(index) {
+            Some((mut buf, _)) => {
+                let l = get_list_len(&mut buf, "", false)?;
+                let mut a = Vec::with_capacity(l);
+                for _ in 0..l {
+                    a.push(f(&mut buf)?);
+                }
+                Ok(a)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use error::ColferError;
+    use types::{ColferTypes, ColferTypesView};
+    use ColferSerializable;
+
+    // Checks every getter of v against the decoded o.
+    fn check(v: &ColferTypesView, o: &ColferTypes) {
+        assert_eq!(v.B(), o.B);
+        assert_eq!(v.U32().unwrap(), o.U32);
+        assert_eq!(v.U64().unwrap(), o.U64);
+        assert_eq!(v.I32().unwrap(), o.I32);
+        assert_eq!(v.I64().unwrap(), o.I64);
+        assert_eq!(v.F32().unwrap(), o.F32);
+        assert_eq!(v.F64().unwrap(), o.F64);
+        assert_eq!(v.T().unwrap(), o.T);
+        assert_eq!(v.S().unwrap(), o.S);
+        assert_eq!(v.A().unwrap(), &o.A[..]);
+        match (v.O().unwrap(), o.O.as_ref()) {
+            (Some(v), Some(o)) => check(&v, o),
+            (None, None) => {}
+            (v, o) => panic!("O is {:?} in the view and {:?} decoded", v.is_some(), o.is_some()),
+        }
+        let os = v.Os().unwrap();
+        assert_eq!(os.len(), o.Os.len());
+        for (v, o) in os.iter().zip(o.Os.iter()) {
+            match o {
+                Some(o) => check(v, o),
+                None => check(v, &ColferTypes::default()),
+            }
+        }
+        assert_eq!(v.Ss().unwrap(), o.Ss);
+        assert_eq!(v.As().unwrap(), o.As);
+        assert_eq!(v.U8().unwrap(), o.U8);
+        assert_eq!(v.U16().unwrap(), o.U16);
+        assert_eq!(v.F32s().unwrap(), o.F32s);
+        assert_eq!(v.F64s().unwrap(), o.F64s);
+        assert_eq!(v.I32s().unwrap(), o.I32s);
+        assert_eq!(v.I64s().unwrap(), o.I64s);
+    }
+
+    fn sample<'a>() -> ColferTypes<'a> {
+        ColferTypes {
+            B: true,
+            U32: 1 << 22,
+            U64: 1 << 60,
+            I32: -3,
+            I64: i64::MIN,
+            F32: 0.5,
+            F64: -1.25,
+            T: Timestamp { seconds: -1, nanos: 2 },
+            S: "text",
+            A: vec![1, 2],
+            O: Some(Box::new(ColferTypes {
+                U16: 300,
+                O: Some(Box::new(ColferTypes { S: "deep", ..ColferTypes::default() })),
+                ..ColferTypes::default()
+            })),
+            Os: vec![None, Some(ColferTypes { U8: 9, Ss: vec!["os"], ..ColferTypes::default() })],
+            Ss: vec!["a", "bcd"],
+            As: vec![&[4][..], &[]],
+            U8: 255,
+            U16: 7,
+            F32s: vec![0.5; 3],
+            F64s: vec![-0.25; 2],
+            I32s: vec![i32::MIN, 7],
+            I64s: vec![-1, 1 << 40],
+            ..ColferTypes::default()
+        }
+    }
+
+    #[test]
+    fn getters_match_decode() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let mut o = ColferTypes::default();
+        o.colf_unmarshal_binary(&data).unwrap();
+        let v = ColferTypesView::new(&data).unwrap();
+        assert_eq!(v.as_bytes(), &data[..]);
+        check(&v, &o);
+    }
+
+    #[test]
+    fn absent_read_as_zero() {
+        let data = [0x7F];
+        let v = ColferTypesView::new(&data).unwrap();
+        check(&v, &ColferTypes::default());
+        assert!(v.O().unwrap().is_none());
+    }
+
+    #[test]
+    fn indices_past_capacity() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let v = ColferView::<4>::new(ColferTypes::DESCRIPTOR, &data).unwrap();
+        assert_eq!(v.as_bytes(), &data[..]);
+        assert!(v.has(0) && v.has(1) && v.has(2) && v.has(3));
+        assert_eq!(v.get_i32(3).unwrap(), -3);
+        for i in 4..=u8::MAX {
+            assert!(!v.has(i), "index {}", i);
+        }
+        assert_eq!(v.get_text(8).unwrap(), "");
+        assert!(v.get_struct::<20>(10, ColferTypes::DESCRIPTOR).unwrap().is_none());
+    }
+
+    #[test]
+    fn nested_views() {
+        let data = sample().colf_marshal_binary().unwrap();
+        let desc = ColferTypes::DESCRIPTOR;
+        let v = ColferView::<20>::new(desc, &data).unwrap();
+
+        let o = v.get_struct::<20>(10, desc).unwrap().unwrap();
+        assert_eq!(o.get_u16(15).unwrap(), 300);
+        let deep = o.get_struct::<20>(10, desc).unwrap().unwrap();
+        assert_eq!((deep.get_text(8).unwrap(), deep.as_bytes()), ("deep", &[8, 4, b'd', b'e', b'e', b'p', 0x7F][..]));
+        assert!(deep.get_struct::<20>(10, desc).unwrap().is_none());
+
+        let os = v.get_structs::<20>(11, desc).unwrap();
+        assert_eq!(os.len(), 2);
+        assert_eq!(os[0].as_bytes(), &[0x7F]);
+        assert_eq!((os[1].get_u8(14).unwrap(), os[1].get_texts(12).unwrap()), (9, vec!["os"]));
+        assert!(v.get_structs::<20>(11, desc).unwrap().iter().all(|o| !o.has(0)));
+    }
+
+    #[test]
+    fn truncated() {
+        let data = sample().colf_marshal_binary().unwrap();
+        for n in 0..data.len() {
+            let r = ColferTypesView::new(&data[..n]);
+            assert!(matches!(r, Err(ColferError::UnexpectedEof)), "cut at {}: {:?}", n, r.map(|v| v.as_bytes()));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use bytes::Buf;
+
+use super::{cursor::Cursor,
+            decode::{get_binary, get_f32, get_f64, get_i32, get_i64, get_list_len, get_text, get_timestamp, get_u16,
+                     get_u32, get_u64, get_u8, get_varint32, get_varint64},
+            descriptor::{ColferType, FieldDescriptor, MessageDescriptor},
+            error::{ColferError, ColferResult},
+            timestamp::Timestamp,
+            varint,
+            ColferSerializable};
+
+/// Receives the fields of an encoded message from `visit`, in wire order. Elements of a
+/// list follow its `visit_list_len` call, one callback each, with the index of the list
+/// field. The fields of a nested data structure come between `enter_struct` and
+/// `leave_struct`, with the indices of the nested type.
+#[allow(unused_variables)]
+pub trait ColferVisitor<'a> {
+    fn visit_bool(&mut self, index: u8, v: bool) {}
+    fn visit_u8(&mut self, index: u8, v: u8) {}
+    fn visit_u16(&mut self, index: u8, v: u16) {}
+    fn visit_u32(&mut self, index: u8, v: u32) {}
+    fn visit_u64(&mut self, index: u8, v: u64) {}
+    fn visit_i32(&mut self, index: u8, v: i32) {}
+    fn visit_i64(&mut self, index: u8, v: i64) {}
+    fn visit_f32(&mut self, index: u8, v: f32) {}
+    fn visit_f64(&mut self, index: u8, v: f64) {}
+    fn visit_timestamp(&mut self, index: u8, v: Timestamp) {}
+    fn visit_text(&mut self, index: u8, v: &'a str) {}
+    fn visit_binary(&mut self, index: u8, v: &'a [u8]) {}
+    fn visit_list_len(&mut self, index: u8, len: usize) {}
+    fn enter_struct(&mut self, index: u8, desc: &'static MessageDescriptor) {}
+    fn leave_struct(&mut self, index: u8) {}
+}
+
+/// Walks the message of type `T` encoded at the start of `data` and returns the number
+/// of bytes read, like `ColferSerializable::colf_unmarshal` but without materializing
+/// any value.
+pub fn visit<'a, T, V>(data: &'a [u8], visitor: &mut V) -> ColferResult<usize>
+where
+    T: ColferSerializable<'a>,
+    V: ColferVisitor<'a>,
+{
+    visit_message(T::DESCRIPTOR, data, visitor)
+}
+
+/// Same as `visit`, for the type described by `desc`.
+pub fn visit_message<'a, V>(desc: &'static MessageDescriptor, data: &'a [u8], visitor: &mut V) -> ColferResult<usize>
+where
+    V: ColferVisitor<'a>,
+{
+    let mut buf = Cursor::new(data);
+    loop {
+        buf_guard!(buf);
+        let header = buf.get_u8();
+        if header == 0x7F {
+            return Ok(buf.position());
+        }
+
+        let byte = buf.position() - 1;
+        let field = match desc.field(header & 0x7F) {
+            Some(field) => field,
+            None => return Err(ColferError::UnknownHeader { byte }),
+        };
+        let flag = header & 0x80 != 0;
+        if field.list {
+            if flag {
+                return Err(ColferError::UnknownHeader { byte });
+            }
+            let l = get_list_len(&mut buf, field.name, false)?;
+            visitor.visit_list_len(field.index, l);
+            for _ in 0..l {
+                // Integer elements have no header to carry the sign, so they are zig-zag encoded.
+                match field.ty {
+                    ColferType::Int32 => visitor.visit_i32(field.index, varint::unzigzag_i32(get_varint32(&mut buf, false)?)),
+                    ColferType::Int64 => visitor.visit_i64(field.index, varint::unzigzag_i64(get_varint64(&mut buf, false)?)),
+                    _ => visit_value(field, false, &mut buf, visitor)?,
+                }
+            }
+        } else {
+            visit_value(field, flag, &mut buf, visitor)?;
+        }
+    }
+}
+
+// Reads one value of the field, or one element for lists. The flag is the 0x80 bit of
+// the header, which selects an alternative encoding for some types.
+fn visit_value<'a, V>(field: &FieldDescriptor, flag: bool, buf: &mut Cursor<'a>, visitor: &mut V) -> ColferResult<()>
+where
+    V: ColferVisitor<'a>,
+{
+    let index = field.index;
+    match (field.ty, flag) {
+        (ColferType::Bool, false) => visitor.visit_bool(index, true),
+        (ColferType::Uint8, false) => visitor.visit_u8(index, get_u8(buf)?),
+        (ColferType::Uint16, flag) => visitor.visit_u16(index, get_u16(buf, flag)?),
+        (ColferType::Uint32, flag) => visitor.visit_u32(index, get_u32(buf, flag)?),
+        (ColferType::Uint64, flag) => visitor.visit_u64(index, get_u64(buf, flag)?),
+        (ColferType::Int32, flag) => visitor.visit_i32(index, get_i32(buf, flag)?),
+        (ColferType::Int64, flag) => visitor.visit_i64(index, get_i64(buf, flag)?),
+        (ColferType::Float32, false) => visitor.visit_f32(index, get_f32(buf)?),
+        (ColferType::Float64, false) => visitor.visit_f64(index, get_f64(buf)?),
+        (ColferType::Timestamp, wide) => visitor.visit_timestamp(index, get_timestamp(buf, wide, false)?),
+        (ColferType::Text, false) => visitor.visit_text(index, get_text(buf, field.name, false)?),
+        (ColferType::Binary, false) => visitor.visit_binary(index, get_binary(buf, field.name, false)?),
+        (ColferType::Struct(desc), false) => {
+            let data: &'a [u8] = buf.get_ref();
+            let start = buf.position();
+            visitor.enter_struct(index, desc);
+            let n = visit_message(desc, &data[start..], visitor)?;
+            visitor.leave_struct(index);
+            buf.set_position(start + n);
+        }
+        _ => {
+            return Err(ColferError::UnknownHeader {
+                byte: buf.position() - 1,
+            })
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use types::ColferTypes;
+
+    // Callback of a visit, with its field index.
+    #[derive(Debug, PartialEq)]
+    enum Event<'a> {
+        Bool(u8, bool),
+        U8(u8, u8),
+        U32(u8, u32),
+        I64(u8, i64),
+        F64(u8, f64),
+        Time(u8, Timestamp),
+        Text(u8, &'a str),
+        Binary(u8, &'a [u8]),
+        ListLen(u8, usize),
+        Enter(u8, &'static str),
+        Leave(u8),
+    }
+
+    #[derive(Default)]
+    struct Record<'a>(Vec<Event<'a>>);
+
+    impl<'a> ColferVisitor<'a> for Record<'a> {
+        fn visit_bool(&mut self, index: u8, v: bool) {
+            self.0.push(Event::Bool(index, v));
+        }
+        fn visit_u8(&mut self, index: u8, v: u8) {
+            self.0.push(Event::U8(index, v));
+        }
+        fn visit_u32(&mut self, index: u8, v: u32) {
+            self.0.push(Event::U32(index, v));
+        }
+        fn visit_i64(&mut self, index: u8, v: i64) {
+            self.0.push(Event::I64(index, v));
+        }
+        fn visit_f64(&mut self, index: u8, v: f64) {
+            self.0.push(Event::F64(index, v));
+        }
+        fn visit_timestamp(&mut self, index: u8, v: Timestamp) {
+            self.0.push(Event::Time(index, v));
+        }
+        fn visit_text(&mut self, index: u8, v: &'a str) {
+            self.0.push(Event::Text(index, v));
+        }
+        fn visit_binary(&mut self, index: u8, v: &'a [u8]) {
+            self.0.push(Event::Binary(index, v));
+        }
+        fn visit_list_len(&mut self, index: u8, len: usize) {
+            self.0.push(Event::ListLen(index, len));
+        }
+        fn enter_struct(&mut self, index: u8, desc: &'static MessageDescriptor) {
+            self.0.push(Event::Enter(index, desc.name));
+        }
+        fn leave_struct(&mut self, index: u8) {
+            self.0.push(Event::Leave(index));
+        }
+    }
+
+    #[test]
+    fn callbacks_in_wire_order() {
+        let o = ColferTypes {
+            B: true,
+            U32: 300,
+            I64: -5,
+            T: Timestamp { seconds: 1, nanos: 2 },
+            S: "top",
+            O: Some(Box::new(ColferTypes {
+                U8: 7,
+                O: Some(Box::new(ColferTypes { A: vec![1, 2], ..ColferTypes::default() })),
+                ..ColferTypes::default()
+            })),
+            Os: vec![None, Some(ColferTypes { S: "os", ..ColferTypes::default() })],
+            Ss: vec!["a", "b"],
+            F64s: vec![0.5],
+            I64s: vec![-1, 1 << 40],
+            ..ColferTypes::default()
+        };
+        let data = o.colf_marshal_binary().unwrap();
+        let mut r = Record::default();
+        assert_eq!(visit::<ColferTypes, _>(&data, &mut r).unwrap(), data.len());
+
+        use self::Event::*;
+        let name = ColferTypes::DESCRIPTOR.name;
+        assert_eq!(
+            r.0,
+            [
+                Bool(0, true),
+                U32(1, 300),
+                I64(4, -5),
+                Time(7, Timestamp { seconds: 1, nanos: 2 }),
+                Text(8, "top"),
+                Enter(10, name),
+                Enter(10, name),
+                Binary(9, &[1, 2]),
+                Leave(10),
+                U8(14, 7),
+                Leave(10),
+                ListLen(11, 2),
+                Enter(11, name),
+                Leave(11),
+                Enter(11, name),
+                Text(8, "os"),
+                Leave(11),
+                ListLen(12, 2),
+                Text(12, "a"),
+                Text(12, "b"),
+                ListLen(17, 1),
+                F64(17, 0.5),
+                ListLen(19, 2),
+                I64(19, -1),
+                I64(19, 1 << 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_what_decode_rejects() {
+        let mut r = Record::default();
+        let r = visit::<ColferTypes, _>(&[0x01, 0x05], &mut r);
+        assert!(matches!(r, Err(ColferError::UnexpectedEof)), "{:?}", r);
+
+        // B takes no flag.
+        let mut r = Record::default();
+        let r = visit::<ColferTypes, _>(&[0x80, 0x7F], &mut r);
+        assert!(matches!(r, Err(ColferError::UnknownHeader { byte: 0 })), "{:?}", r);
+    }
+}
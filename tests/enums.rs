@@ -0,0 +1,57 @@
+extern crate colfer_rs;
+
+// Generated by `colf gen -o tests/gen tests/enums.colf`.
+#[path = "gen/enums.rs"]
+#[allow(dead_code)]
+mod enums;
+
+use colfer_rs::{ColferError, ColferSerializable};
+use enums::{Level, Member, State, MEMBER};
+
+#[test]
+fn named_values_round_trip() {
+    let want = Member {
+        level: Level::Admin,
+        state: State::Active,
+        ..Member::default()
+    };
+    let data = want.colf_marshal_binary().unwrap();
+    assert_eq!(data, [0x00, 9, 0x81, 2, 0x7F]);
+    let mut got = Member::default();
+    assert_eq!(got.colf_unmarshal_binary(&data).unwrap(), data.len());
+    assert_eq!(got, want);
+}
+
+#[test]
+fn absent_decodes_as_zero() {
+    let mut got = Member {
+        level: Level::Member,
+        state: State::Unknown(7),
+        ..Member::default()
+    };
+    got.colf_unmarshal_into(&[0x7F]).unwrap();
+    assert_eq!(got.level, Level::Guest);
+    assert_eq!(got.state, State::Invited);
+}
+
+#[test]
+fn unknown_rejected() {
+    let r = Member::default().colf_unmarshal_binary(&[0x81, 2, 0x00, 5, 0x7F]);
+    assert!(matches!(r, Err(ColferError::InvalidValue { byte: 2 })), "{:?}", r);
+}
+
+#[test]
+fn unknown_kept() {
+    for &(ref data, x) in [(vec![0x81, 7, 0x7F], 7), (vec![0x01, 0x12, 0x34, 0x7F], 0x1234)].iter() {
+        let mut got = Member::default();
+        assert_eq!(got.colf_unmarshal_binary(data).unwrap(), data.len());
+        assert_eq!(got.state, State::Unknown(x));
+        assert_eq!(&got.colf_marshal_binary().unwrap(), data);
+    }
+}
+
+#[test]
+fn docs_without_annotations() {
+    assert_eq!(MEMBER.fields[0].doc, "Level is the access of the member.");
+    assert_eq!(MEMBER.fields[1].doc, "State is where the member is in sign-up.");
+}
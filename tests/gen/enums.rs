@@ -0,0 +1,274 @@
+// Code generated by colf from package enums. DO NOT EDIT.
+//! Package enums has fields generated as Rust enums.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Named values of `member.level`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Level {
+    #[default]
+    Guest = 0,
+    Member = 1,
+    Admin = 9,
+}
+
+impl TryFrom<u8> for Level {
+    type Error = u8;
+
+    /// Fails with the value when it has no name.
+    fn try_from(x: u8) -> Result<Self, u8> {
+        match x {
+            0 => Ok(Level::Guest),
+            1 => Ok(Level::Member),
+            9 => Ok(Level::Admin),
+            x => Err(x),
+        }
+    }
+}
+
+impl From<Level> for u8 {
+    fn from(x: Level) -> Self {
+        match x {
+            Level::Guest => 0,
+            Level::Member => 1,
+            Level::Admin => 9,
+        }
+    }
+}
+
+/// Named values of `member.state`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum State {
+    #[default]
+    Invited = 0,
+    Active = 2,
+    /// Value without a name in this version of the schema.
+    Unknown(u16) = 1,
+}
+
+impl From<u16> for State {
+    fn from(x: u16) -> Self {
+        match x {
+            0 => State::Invited,
+            2 => State::Active,
+            x => State::Unknown(x),
+        }
+    }
+}
+
+impl From<State> for u16 {
+    fn from(x: State) -> Self {
+        match x {
+            State::Invited => 0,
+            State::Active => 2,
+            State::Unknown(x) => x,
+        }
+    }
+}
+
+/// Member has a level from a closed set and a state that may grow.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Member<'a> {
+    /// Level is the access of the member.
+    pub level: Level,
+    /// State is where the member is in sign-up.
+    pub state: State,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static MEMBER: MessageDescriptor = MessageDescriptor {
+    name: "member",
+    doc: "Member has a level from a closed set and a state that may grow.",
+    fields: &[
+        FieldDescriptor {
+            name: "level",
+            index: 0,
+            ty: ColferType::Uint8,
+            list: false,
+            doc: "Level is the access of the member.",
+        },
+        FieldDescriptor {
+            name: "state",
+            index: 1,
+            ty: ColferType::Uint16,
+            list: false,
+            doc: "State is where the member is in sign-up.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Member<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &MEMBER;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if u8::from(self.level) != 0 {
+            buf.put_u8(0);
+            buf.put_u8(u8::from(self.level));
+        }
+
+        if u16::from(self.state) >= 1 << 8 {
+            buf.put_u8(1);
+            buf.put_u16(u16::from(self.state));
+        } else if u16::from(self.state) != 0 {
+            buf.put_u8(1 | 0x80);
+            buf.put_u8(u16::from(self.state) as u8);
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        if u8::from(self.level) != 0 {
+            l += 2;
+        }
+
+        if u16::from(self.state) >= 1 << 8 {
+            l += 3;
+        } else if u16::from(self.state) != 0 {
+            l += 2;
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.level = Default::default();
+        self.state = Default::default();
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Member<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        if reuse {
+            self.colf_reset();
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = MEMBER.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_u8(&mut buf)?;
+                    if canonical && x == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.level = TryFrom::try_from(x).map_err(|_| ColferError::InvalidValue { byte })?;
+                }
+                1 => {
+                    let x = get_u16(&mut buf, false)?;
+                    if canonical && x < 1 << 8 {
+                        return Err(ColferError::NonCanonicalForm { byte });
+                    }
+                    self.state = TryFrom::try_from(x).map_err(|_| ColferError::InvalidValue { byte })?;
+                }
+                129 => {
+                    // 1 | 0x80
+                    let x = get_u16(&mut buf, true)?;
+                    if canonical && x == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.state = TryFrom::try_from(x).map_err(|_| ColferError::InvalidValue { byte })?;
+                }
+                0x7F => {
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= MEMBER.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Member`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct MemberView<'a>(pub(crate) ColferView<'a, 2>);
+
+impl<'a> MemberView<'a> {
+    /// Indexes the `Member` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&MEMBER, data).map(MemberView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn level(&self) -> ColferResult<u8> {
+        self.0.get_u8(0)
+    }
+
+    pub fn state(&self) -> ColferResult<u16> {
+        self.0.get_u16(1)
+    }
+}
@@ -0,0 +1,293 @@
+// Code generated by colf from package forest. DO NOT EDIT.
+//! Package forest refers to package grove, which refers back.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Tree holds leaves of the other package.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tree<'a> {
+    /// Name is a text.
+    pub name: &'a str,
+    /// Leaves refer to package grove.
+    pub leaves: Vec<Option<super::grove::Leaf<'a>>>,
+    /// Top refers to package grove.
+    pub top: Option<Box<super::grove::Leaf<'a>>>,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static TREE: MessageDescriptor = MessageDescriptor {
+    name: "tree",
+    doc: "Tree holds leaves of the other package.",
+    fields: &[
+        FieldDescriptor {
+            name: "name",
+            index: 0,
+            ty: ColferType::Text,
+            list: false,
+            doc: "Name is a text.",
+        },
+        FieldDescriptor {
+            name: "leaves",
+            index: 1,
+            ty: ColferType::Struct(&super::grove::LEAF),
+            list: true,
+            doc: "Leaves refer to package grove.",
+        },
+        FieldDescriptor {
+            name: "top",
+            index: 2,
+            ty: ColferType::Struct(&super::grove::LEAF),
+            list: false,
+            doc: "Top refers to package grove.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Tree<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &TREE;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if !self.name.is_empty() {
+            buf.put_u8(0);
+            varint::put_usize(buf, self.name.len());
+            buf.put_slice(self.name.as_bytes());
+        }
+
+        if !self.leaves.is_empty() {
+            buf.put_u8(1);
+            varint::put_usize(buf, self.leaves.len());
+            for o in self.leaves.iter() {
+                match *o {
+                    Some(ref o) => {
+                        o.colf_marshal_to(buf);
+                    }
+                    None => buf.put_u8(0x7F),
+                }
+            }
+        }
+
+        if let Some(ref o) = self.top {
+            buf.put_u8(2);
+            o.colf_marshal_to(buf);
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        {
+            let x = self.name.len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::name",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.leaves.len();
+            if x > 0 {
+                if x > COLFER_LIST_MAX {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::leaves",
+                        overflow: x - COLFER_LIST_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for o in self.leaves.iter() {
+                    match *o {
+                        Some(ref v) => l += v.colf_marshal_len()?,
+                        None => l += 1,
+                    }
+                }
+
+                if l > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::leaves",
+                        overflow: l - COLFER_SIZE_MAX,
+                    });
+                }
+            }
+        }
+
+        if let Some(ref v) = self.top {
+            l += v.colf_marshal_len()? + 1;
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.name = "";
+        self.leaves.clear();
+        self.top = None;
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Tree<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        let mut seen = [false; 2];
+        if reuse {
+            let kept = (mem::take(&mut self.leaves), mem::take(&mut self.top));
+            self.colf_reset();
+            self.leaves = kept.0;
+            self.top = kept.1;
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = TREE.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_text(&mut buf, "self::name", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.name = x;
+                }
+                1 => {
+                    let l = get_list_len(&mut buf, "self::leaves", canonical)?;
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    seen[0] = true;
+                    self.leaves.truncate(l);
+                    self.leaves.reserve(l - self.leaves.len());
+                    for i in 0..l {
+                        if i == self.leaves.len() {
+                            self.leaves.push(None);
+                        }
+                        let obj = self.leaves[i].get_or_insert_with(Default::default);
+                        let start = buf.position();
+                        let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                        buf.set_position(start + n);
+                    }
+                }
+                2 => {
+                    seen[1] = true;
+                    let obj = self.top.get_or_insert_with(Box::default);
+                    let start = buf.position();
+                    let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                    buf.set_position(start + n);
+                }
+                0x7F => {
+                    if reuse && !seen[0] {
+                        self.leaves.clear();
+                    }
+                    if reuse && !seen[1] {
+                        self.top = None;
+                    }
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= TREE.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        if reuse && !seen[0] {
+                            self.leaves.clear();
+                        }
+                        if reuse && !seen[1] {
+                            self.top = None;
+                        }
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Tree`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct TreeView<'a>(pub(crate) ColferView<'a, 3>);
+
+impl<'a> TreeView<'a> {
+    /// Indexes the `Tree` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&TREE, data).map(TreeView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn name(&self) -> ColferResult<&'a str> {
+        self.0.get_text(0)
+    }
+
+    pub fn leaves(&self) -> ColferResult<Vec<super::grove::LeafView<'a>>> {
+        Ok(self.0.get_structs(1, &super::grove::LEAF)?.into_iter().map(super::grove::LeafView).collect())
+    }
+
+    pub fn top(&self) -> ColferResult<Option<super::grove::LeafView<'a>>> {
+        Ok(self.0.get_struct(2, &super::grove::LEAF)?.map(super::grove::LeafView))
+    }
+}
@@ -0,0 +1,293 @@
+// Code generated by colf from package grove. DO NOT EDIT.
+//! Package grove refers to package forest, which refers back.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Leaf holds trees of the other package.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Leaf<'a> {
+    /// Label is a text.
+    pub label: &'a str,
+    /// Tree refers to package forest.
+    pub tree: Option<Box<super::forest::Tree<'a>>>,
+    /// Trees refer to package forest.
+    pub trees: Vec<Option<super::forest::Tree<'a>>>,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static LEAF: MessageDescriptor = MessageDescriptor {
+    name: "leaf",
+    doc: "Leaf holds trees of the other package.",
+    fields: &[
+        FieldDescriptor {
+            name: "label",
+            index: 0,
+            ty: ColferType::Text,
+            list: false,
+            doc: "Label is a text.",
+        },
+        FieldDescriptor {
+            name: "tree",
+            index: 1,
+            ty: ColferType::Struct(&super::forest::TREE),
+            list: false,
+            doc: "Tree refers to package forest.",
+        },
+        FieldDescriptor {
+            name: "trees",
+            index: 2,
+            ty: ColferType::Struct(&super::forest::TREE),
+            list: true,
+            doc: "Trees refer to package forest.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Leaf<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &LEAF;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if !self.label.is_empty() {
+            buf.put_u8(0);
+            varint::put_usize(buf, self.label.len());
+            buf.put_slice(self.label.as_bytes());
+        }
+
+        if let Some(ref o) = self.tree {
+            buf.put_u8(1);
+            o.colf_marshal_to(buf);
+        }
+
+        if !self.trees.is_empty() {
+            buf.put_u8(2);
+            varint::put_usize(buf, self.trees.len());
+            for o in self.trees.iter() {
+                match *o {
+                    Some(ref o) => {
+                        o.colf_marshal_to(buf);
+                    }
+                    None => buf.put_u8(0x7F),
+                }
+            }
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        {
+            let x = self.label.len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::label",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        if let Some(ref v) = self.tree {
+            l += v.colf_marshal_len()? + 1;
+        }
+
+        {
+            let x = self.trees.len();
+            if x > 0 {
+                if x > COLFER_LIST_MAX {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::trees",
+                        overflow: x - COLFER_LIST_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for o in self.trees.iter() {
+                    match *o {
+                        Some(ref v) => l += v.colf_marshal_len()?,
+                        None => l += 1,
+                    }
+                }
+
+                if l > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::trees",
+                        overflow: l - COLFER_SIZE_MAX,
+                    });
+                }
+            }
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.label = "";
+        self.tree = None;
+        self.trees.clear();
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Leaf<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        let mut seen = [false; 2];
+        if reuse {
+            let kept = (mem::take(&mut self.tree), mem::take(&mut self.trees));
+            self.colf_reset();
+            self.tree = kept.0;
+            self.trees = kept.1;
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = LEAF.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_text(&mut buf, "self::label", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.label = x;
+                }
+                1 => {
+                    seen[0] = true;
+                    let obj = self.tree.get_or_insert_with(Box::default);
+                    let start = buf.position();
+                    let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                    buf.set_position(start + n);
+                }
+                2 => {
+                    let l = get_list_len(&mut buf, "self::trees", canonical)?;
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    seen[1] = true;
+                    self.trees.truncate(l);
+                    self.trees.reserve(l - self.trees.len());
+                    for i in 0..l {
+                        if i == self.trees.len() {
+                            self.trees.push(None);
+                        }
+                        let obj = self.trees[i].get_or_insert_with(Default::default);
+                        let start = buf.position();
+                        let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                        buf.set_position(start + n);
+                    }
+                }
+                0x7F => {
+                    if reuse && !seen[0] {
+                        self.tree = None;
+                    }
+                    if reuse && !seen[1] {
+                        self.trees.clear();
+                    }
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= LEAF.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        if reuse && !seen[0] {
+                            self.tree = None;
+                        }
+                        if reuse && !seen[1] {
+                            self.trees.clear();
+                        }
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Leaf`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct LeafView<'a>(pub(crate) ColferView<'a, 3>);
+
+impl<'a> LeafView<'a> {
+    /// Indexes the `Leaf` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&LEAF, data).map(LeafView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn label(&self) -> ColferResult<&'a str> {
+        self.0.get_text(0)
+    }
+
+    pub fn tree(&self) -> ColferResult<Option<super::forest::TreeView<'a>>> {
+        Ok(self.0.get_struct(1, &super::forest::TREE)?.map(super::forest::TreeView))
+    }
+
+    pub fn trees(&self) -> ColferResult<Vec<super::forest::TreeView<'a>>> {
+        Ok(self.0.get_structs(2, &super::forest::TREE)?.into_iter().map(super::forest::TreeView).collect())
+    }
+}
@@ -0,0 +1,345 @@
+// Code generated by colf from package limits. DO NOT EDIT.
+//! Package limits has fields with lower limits than the global ones.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Profile has limited texts, binaries and lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile<'a> {
+    /// Name holds up to 8 bytes.
+    pub name: &'a str,
+    /// Avatar holds up to 4 bytes.
+    pub avatar: Vec<u8>,
+    /// Roles holds up to 3 roles of up to 4 bytes each.
+    pub roles: Vec<&'a str>,
+    /// Scores holds up to 2 values.
+    pub scores: Vec<i32>,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static PROFILE: MessageDescriptor = MessageDescriptor {
+    name: "profile",
+    doc: "Profile has limited texts, binaries and lists.",
+    fields: &[
+        FieldDescriptor {
+            name: "name",
+            index: 0,
+            ty: ColferType::Text,
+            list: false,
+            doc: "Name holds up to 8 bytes.",
+        },
+        FieldDescriptor {
+            name: "avatar",
+            index: 1,
+            ty: ColferType::Binary,
+            list: false,
+            doc: "Avatar holds up to 4 bytes.",
+        },
+        FieldDescriptor {
+            name: "roles",
+            index: 2,
+            ty: ColferType::Text,
+            list: true,
+            doc: "Roles holds up to 3 roles of up to 4 bytes each.",
+        },
+        FieldDescriptor {
+            name: "scores",
+            index: 3,
+            ty: ColferType::Int32,
+            list: true,
+            doc: "Scores holds up to 2 values.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Profile<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &PROFILE;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if !self.name.is_empty() {
+            buf.put_u8(0);
+            varint::put_usize(buf, self.name.len());
+            buf.put_slice(self.name.as_bytes());
+        }
+
+        if !self.avatar.is_empty() {
+            buf.put_u8(1);
+            varint::put_usize(buf, self.avatar.len());
+            buf.put_slice(&self.avatar);
+        }
+
+        if !self.roles.is_empty() {
+            buf.put_u8(2);
+            varint::put_usize(buf, self.roles.len());
+            for x in self.roles.iter() {
+                varint::put_usize(buf, x.len());
+                buf.put_slice(x.as_bytes());
+            }
+        }
+
+        if !self.scores.is_empty() {
+            buf.put_u8(3);
+            varint::put_usize(buf, self.scores.len());
+            for &x in self.scores.iter() {
+                varint::put_u32(buf, varint::zigzag_i32(x));
+            }
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        {
+            let x = self.name.len();
+            if x > 0 {
+                if x > 8 {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::name",
+                        overflow: x - 8,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.avatar.len();
+            if x > 0 {
+                if x > 4 {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::avatar",
+                        overflow: x - 4,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.roles.len();
+            if x > 0 {
+                if x > 3 {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::roles",
+                        overflow: x - 3,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for a in self.roles.iter() {
+                    let xs = a.len();
+                    if xs > 4 {
+                        return Err(ColferError::MaxSizeBreach {
+                            field: "self::roles",
+                            overflow: xs - 4,
+                        });
+                    }
+                    l += varint::size_usize(xs) + xs;
+                }
+
+                if l > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::roles",
+                        overflow: l - COLFER_SIZE_MAX,
+                    });
+                }
+            }
+        }
+
+        {
+            let x = self.scores.len();
+            if x > 0 {
+                if x > 2 {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::scores",
+                        overflow: x - 2,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for &v in self.scores.iter() {
+                    l += varint::size_u32(varint::zigzag_i32(v));
+                }
+            }
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.name = "";
+        self.avatar.clear();
+        self.roles.clear();
+        self.scores.clear();
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Profile<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        if reuse {
+            self.colf_reset();
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = PROFILE.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_text(&mut buf, "self::name", canonical)?;
+                    if x.len() > 8 {
+                        return Err(ColferError::MaxSizeBreach { field: "self::name", overflow: x.len() - 8 });
+                    }
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.name = x;
+                }
+                1 => {
+                    let x = get_binary(&mut buf, "self::avatar", canonical)?;
+                    if x.len() > 4 {
+                        return Err(ColferError::MaxSizeBreach { field: "self::avatar", overflow: x.len() - 4 });
+                    }
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.avatar.clear();
+                    self.avatar.extend_from_slice(x);
+                }
+                2 => {
+                    let l = get_list_len(&mut buf, "self::roles", canonical)?;
+                    if l > 3 {
+                        return Err(ColferError::MaxListBreach { field: "self::roles", overflow: l - 3 });
+                    }
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.roles.clear();
+                    self.roles.reserve(l);
+                    let start = buf.position();
+                    let n = bulk::split_texts(data, start, l, "self::roles", canonical, &mut self.roles)?;
+                    buf.advance(n);
+                    if let Some(a) = self.roles.iter().find(|a| a.len() > 4) {
+                        return Err(ColferError::MaxSizeBreach { field: "self::roles", overflow: a.len() - 4 });
+                    }
+                }
+                3 => {
+                    let l = get_list_len(&mut buf, "self::scores", canonical)?;
+                    if l > 2 {
+                        return Err(ColferError::MaxListBreach { field: "self::scores", overflow: l - 2 });
+                    }
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.scores.clear();
+                    self.scores.reserve(l);
+                    for _ in 0..l {
+                        self.scores.push(varint::unzigzag_i32(get_varint32(&mut buf, canonical)?));
+                    }
+                }
+                0x7F => {
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= PROFILE.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Profile`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileView<'a>(pub(crate) ColferView<'a, 4>);
+
+impl<'a> ProfileView<'a> {
+    /// Indexes the `Profile` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&PROFILE, data).map(ProfileView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn name(&self) -> ColferResult<&'a str> {
+        self.0.get_text(0)
+    }
+
+    pub fn avatar(&self) -> ColferResult<&'a [u8]> {
+        self.0.get_binary(1)
+    }
+
+    pub fn roles(&self) -> ColferResult<Vec<&'a str>> {
+        self.0.get_texts(2)
+    }
+
+    pub fn scores(&self) -> ColferResult<Vec<i32>> {
+        self.0.get_i32s(3)
+    }
+}
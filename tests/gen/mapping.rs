@@ -0,0 +1,216 @@
+// Code generated by colf from package mapping. DO NOT EDIT.
+//! Package mapping has fields generated with other Rust types.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Record is identified by a fixed-size ID.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record<'a> {
+    /// ID is generated as 16 bytes.
+    pub id: [u8; 16],
+    /// Name keeps its default type.
+    pub name: &'a str,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static RECORD: MessageDescriptor = MessageDescriptor {
+    name: "record",
+    doc: "Record is identified by a fixed-size ID.",
+    fields: &[
+        FieldDescriptor {
+            name: "id",
+            index: 0,
+            ty: ColferType::Binary,
+            list: false,
+            doc: "ID is generated as 16 bytes.",
+        },
+        FieldDescriptor {
+            name: "name",
+            index: 1,
+            ty: ColferType::Text,
+            list: false,
+            doc: "Name keeps its default type.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Record<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &RECORD;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if !AsRef::<[u8]>::as_ref(&self.id).is_empty() {
+            buf.put_u8(0);
+            varint::put_usize(buf, AsRef::<[u8]>::as_ref(&self.id).len());
+            buf.put_slice(AsRef::<[u8]>::as_ref(&self.id));
+        }
+
+        if !self.name.is_empty() {
+            buf.put_u8(1);
+            varint::put_usize(buf, self.name.len());
+            buf.put_slice(self.name.as_bytes());
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        {
+            let x = AsRef::<[u8]>::as_ref(&self.id).len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::id",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.name.len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::name",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.id = Default::default();
+        self.name = "";
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Record<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        if reuse {
+            self.colf_reset();
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = RECORD.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_binary(&mut buf, "self::id", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.id = TryFrom::try_from(x).map_err(|_| ColferError::InvalidValue { byte })?;
+                }
+                1 => {
+                    let x = get_text(&mut buf, "self::name", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.name = x;
+                }
+                0x7F => {
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= RECORD.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Record`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordView<'a>(pub(crate) ColferView<'a, 2>);
+
+impl<'a> RecordView<'a> {
+    /// Indexes the `Record` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&RECORD, data).map(RecordView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn id(&self) -> ColferResult<&'a [u8]> {
+        self.0.get_binary(0)
+    }
+
+    pub fn name(&self) -> ColferResult<&'a str> {
+        self.0.get_text(1)
+    }
+}
@@ -0,0 +1,447 @@
+// Code generated by colf from package reuse. DO NOT EDIT.
+//! Package reuse has data structures with nested allocations, for decoding with reuse.
+#![allow(unused_imports, non_snake_case, clippy::unnecessary_fallible_conversions)]
+
+use colfer_rs::rt::{get_binary, get_f32, get_f64, get_list_len, get_text, get_timestamp, get_u16, get_u32, get_u64, get_u8, get_varint32,
+                    get_varint64, skip_field, mem, Box, Buf, BufMut, Cursor, TryFrom, Vec};
+use colfer_rs::{bulk, varint, ColferError, ColferResult, ColferSerializable, ColferType, ColferView, DecodeOptions, FieldDescriptor,
+                MessageDescriptor, Timestamp, UnknownFields, COLFER_LIST_MAX, COLFER_SIZE_MAX};
+
+/// Node holds a tree of nodes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Node<'a> {
+    /// Name is a text.
+    pub name: &'a str,
+    /// Data is a binary, which owns its bytes.
+    pub data: Vec<u8>,
+    /// Tags are texts.
+    pub tags: Vec<&'a str>,
+    /// Values are integers.
+    pub values: Vec<i64>,
+    /// Child is a nested node.
+    pub child: Option<Box<Node<'a>>>,
+    /// Children are nested nodes.
+    pub children: Vec<Option<Node<'a>>>,
+    /// Fields unknown to this version of the schema, kept as encoded by
+    /// `UnknownFields::Preserve` decoding and emitted again on marshal.
+    pub unknown_fields: &'a [u8],
+}
+
+pub static NODE: MessageDescriptor = MessageDescriptor {
+    name: "node",
+    doc: "Node holds a tree of nodes.",
+    fields: &[
+        FieldDescriptor {
+            name: "name",
+            index: 0,
+            ty: ColferType::Text,
+            list: false,
+            doc: "Name is a text.",
+        },
+        FieldDescriptor {
+            name: "data",
+            index: 1,
+            ty: ColferType::Binary,
+            list: false,
+            doc: "Data is a binary, which owns its bytes.",
+        },
+        FieldDescriptor {
+            name: "tags",
+            index: 2,
+            ty: ColferType::Text,
+            list: true,
+            doc: "Tags are texts.",
+        },
+        FieldDescriptor {
+            name: "values",
+            index: 3,
+            ty: ColferType::Int64,
+            list: true,
+            doc: "Values are integers.",
+        },
+        FieldDescriptor {
+            name: "child",
+            index: 4,
+            ty: ColferType::Struct(&NODE),
+            list: false,
+            doc: "Child is a nested node.",
+        },
+        FieldDescriptor {
+            name: "children",
+            index: 5,
+            ty: ColferType::Struct(&NODE),
+            list: true,
+            doc: "Children are nested nodes.",
+        },
+    ],
+};
+
+impl<'a> ColferSerializable<'a> for Node<'a> {
+    const DESCRIPTOR: &'static MessageDescriptor = &NODE;
+
+    fn colf_marshal_to<B: BufMut>(&self, buf: &mut B) -> usize {
+        let remaining = buf.remaining_mut();
+        if !self.name.is_empty() {
+            buf.put_u8(0);
+            varint::put_usize(buf, self.name.len());
+            buf.put_slice(self.name.as_bytes());
+        }
+
+        if !self.data.is_empty() {
+            buf.put_u8(1);
+            varint::put_usize(buf, self.data.len());
+            buf.put_slice(&self.data);
+        }
+
+        if !self.tags.is_empty() {
+            buf.put_u8(2);
+            varint::put_usize(buf, self.tags.len());
+            for x in self.tags.iter() {
+                varint::put_usize(buf, x.len());
+                buf.put_slice(x.as_bytes());
+            }
+        }
+
+        if !self.values.is_empty() {
+            buf.put_u8(3);
+            varint::put_usize(buf, self.values.len());
+            for &x in self.values.iter() {
+                varint::put_u64(buf, varint::zigzag_i64(x));
+            }
+        }
+
+        if let Some(ref o) = self.child {
+            buf.put_u8(4);
+            o.colf_marshal_to(buf);
+        }
+
+        if !self.children.is_empty() {
+            buf.put_u8(5);
+            varint::put_usize(buf, self.children.len());
+            for o in self.children.iter() {
+                match *o {
+                    Some(ref o) => {
+                        o.colf_marshal_to(buf);
+                    }
+                    None => buf.put_u8(0x7F),
+                }
+            }
+        }
+
+        buf.put_slice(self.unknown_fields);
+        buf.put_u8(0x7F);
+        remaining - buf.remaining_mut()
+    }
+
+    fn colf_marshal_len(&self) -> ColferResult<usize> {
+        let mut l = 1 + self.unknown_fields.len();
+
+        {
+            let x = self.name.len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::name",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.data.len();
+            if x > 0 {
+                if x > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::data",
+                        overflow: x - COLFER_SIZE_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x) + x;
+            }
+        }
+
+        {
+            let x = self.tags.len();
+            if x > 0 {
+                if x > COLFER_LIST_MAX {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::tags",
+                        overflow: x - COLFER_LIST_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for a in self.tags.iter() {
+                    let xs = a.len();
+                    if xs > COLFER_SIZE_MAX {
+                        return Err(ColferError::MaxSizeBreach {
+                            field: "self::tags",
+                            overflow: xs - COLFER_SIZE_MAX,
+                        });
+                    }
+                    l += varint::size_usize(xs) + xs;
+                }
+
+                if l > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::tags",
+                        overflow: l - COLFER_SIZE_MAX,
+                    });
+                }
+            }
+        }
+
+        {
+            let x = self.values.len();
+            if x > 0 {
+                if x > COLFER_LIST_MAX {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::values",
+                        overflow: x - COLFER_LIST_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for &v in self.values.iter() {
+                    l += varint::size_u64(varint::zigzag_i64(v));
+                }
+            }
+        }
+
+        if let Some(ref v) = self.child {
+            l += v.colf_marshal_len()? + 1;
+        }
+
+        {
+            let x = self.children.len();
+            if x > 0 {
+                if x > COLFER_LIST_MAX {
+                    return Err(ColferError::MaxListBreach {
+                        field: "self::children",
+                        overflow: x - COLFER_LIST_MAX,
+                    });
+                }
+                l += 1 + varint::size_usize(x);
+                for o in self.children.iter() {
+                    match *o {
+                        Some(ref v) => l += v.colf_marshal_len()?,
+                        None => l += 1,
+                    }
+                }
+
+                if l > COLFER_SIZE_MAX {
+                    return Err(ColferError::MaxSizeBreach {
+                        field: "self::children",
+                        overflow: l - COLFER_SIZE_MAX,
+                    });
+                }
+            }
+        }
+
+        if l > COLFER_SIZE_MAX {
+            return Err(ColferError::MaxSizeBreach {
+                field: "self",
+                overflow: l - COLFER_SIZE_MAX,
+            });
+        }
+
+        Ok(l)
+    }
+
+    fn colf_reset(&mut self) {
+        self.name = "";
+        self.data.clear();
+        self.tags.clear();
+        self.values.clear();
+        self.child = None;
+        self.children.clear();
+        self.unknown_fields = &[];
+    }
+
+    fn colf_unmarshal_with(&mut self, data: &'a [u8], opts: &DecodeOptions) -> ColferResult<usize> {
+        self.colf_decode(data, opts, false)
+    }
+
+    fn colf_unmarshal_into(&mut self, data: &'a [u8]) -> ColferResult<usize> {
+        self.colf_decode(data, &DecodeOptions::default(), true)
+    }
+}
+
+impl<'a> Node<'a> {
+    // Decodes like colf_unmarshal_with, or like colf_unmarshal_into when reuse is set. The
+    // nested data structures then get decoded into rather than replaced.
+    pub(crate) fn colf_decode(&mut self, data: &'a [u8], opts: &DecodeOptions, reuse: bool) -> ColferResult<usize> {
+        let len = data.len();
+        if len == 0 {
+            return Err(ColferError::UnexpectedEof);
+        }
+        let mut buf = Cursor::new(data);
+        let canonical = opts.validate_canonical;
+        let mut last_index = None;
+        self.unknown_fields = &[];
+        let mut seen = [false; 2];
+        if reuse {
+            let kept = (mem::take(&mut self.child), mem::take(&mut self.children));
+            self.colf_reset();
+            self.child = kept.0;
+            self.children = kept.1;
+        }
+
+        loop {
+            if !buf.has_remaining() {
+                return Err(ColferError::UnexpectedEof);
+            }
+            let header = buf.get_u8();
+            let byte = buf.position() - 1;
+            if canonical && header != 0x7F {
+                let index = header & 0x7F;
+                match last_index {
+                    Some(last) if index == last => {
+                        return Err(ColferError::DuplicateField { byte });
+                    }
+                    Some(last) if index < last => {
+                        return Err(ColferError::HeaderOrder { byte });
+                    }
+                    _ => last_index = Some(index),
+                }
+            }
+            if header != 0x7F && !opts.fields.contains(header & 0x7F) {
+                if let Some(field) = NODE.field(header & 0x7F) {
+                    skip_field(field, header & 0x80 != 0, &mut buf)?;
+                    continue;
+                }
+            }
+
+            match header {
+                0 => {
+                    let x = get_text(&mut buf, "self::name", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.name = x;
+                }
+                1 => {
+                    let x = get_binary(&mut buf, "self::data", canonical)?;
+                    if canonical && x.is_empty() {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.data.clear();
+                    self.data.extend_from_slice(x);
+                }
+                2 => {
+                    let l = get_list_len(&mut buf, "self::tags", canonical)?;
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.tags.clear();
+                    self.tags.reserve(l);
+                    let start = buf.position();
+                    let n = bulk::split_texts(data, start, l, "self::tags", canonical, &mut self.tags)?;
+                    buf.advance(n);
+                }
+                3 => {
+                    let l = get_list_len(&mut buf, "self::values", canonical)?;
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    self.values.clear();
+                    self.values.reserve(l);
+                    for _ in 0..l {
+                        self.values.push(varint::unzigzag_i64(get_varint64(&mut buf, canonical)?));
+                    }
+                }
+                4 => {
+                    seen[0] = true;
+                    let obj = self.child.get_or_insert_with(Box::default);
+                    let start = buf.position();
+                    let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                    buf.set_position(start + n);
+                }
+                5 => {
+                    let l = get_list_len(&mut buf, "self::children", canonical)?;
+                    if canonical && l == 0 {
+                        return Err(ColferError::ZeroValue { byte });
+                    }
+                    seen[1] = true;
+                    self.children.truncate(l);
+                    self.children.reserve(l - self.children.len());
+                    for i in 0..l {
+                        if i == self.children.len() {
+                            self.children.push(None);
+                        }
+                        let obj = self.children[i].get_or_insert_with(Default::default);
+                        let start = buf.position();
+                        let n = obj.colf_decode(&data[start..], &opts.nested(), true).map_err(|e| e.offset(start))?;
+                        buf.set_position(start + n);
+                    }
+                }
+                0x7F => {
+                    if reuse && !seen[0] {
+                        self.child = None;
+                    }
+                    if reuse && !seen[1] {
+                        self.children.clear();
+                    }
+                    return Ok(buf.position());
+                }
+                _ => {
+                    // Nothing tells how long unknown fields are, so they can only be kept
+                    // when the data is known to end with the terminator of this message.
+                    // Headers of known fields with a flag they don't take stay invalid.
+                    let unknown = usize::from(header & 0x7F) >= NODE.fields.len();
+                    if unknown && opts.unknown_fields == UnknownFields::Preserve && opts.whole_input && data[len - 1] == 0x7F {
+                        self.unknown_fields = &data[byte..len - 1];
+                        if reuse && !seen[0] {
+                            self.child = None;
+                        }
+                        if reuse && !seen[1] {
+                            self.children.clear();
+                        }
+                        return Ok(len);
+                    }
+                    return Err(ColferError::UnknownHeader { byte });
+                }
+            }
+        }
+    }
+}
+
+/// Lazy, zero-copy view of an encoded `Node`. See `ColferView`.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeView<'a>(pub(crate) ColferView<'a, 6>);
+
+impl<'a> NodeView<'a> {
+    /// Indexes the `Node` encoded at the start of `data`.
+    pub fn new(data: &'a [u8]) -> ColferResult<Self> {
+        ColferView::new(&NODE, data).map(NodeView)
+    }
+
+    /// Returns the encoded message, terminator included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn name(&self) -> ColferResult<&'a str> {
+        self.0.get_text(0)
+    }
+
+    pub fn data(&self) -> ColferResult<&'a [u8]> {
+        self.0.get_binary(1)
+    }
+
+    pub fn tags(&self) -> ColferResult<Vec<&'a str>> {
+        self.0.get_texts(2)
+    }
+
+    pub fn values(&self) -> ColferResult<Vec<i64>> {
+        self.0.get_i64s(3)
+    }
+
+    pub fn child(&self) -> ColferResult<Option<NodeView<'a>>> {
+        Ok(self.0.get_struct(4, &NODE)?.map(NodeView))
+    }
+
+    pub fn children(&self) -> ColferResult<Vec<NodeView<'a>>> {
+        Ok(self.0.get_structs(5, &NODE)?.into_iter().map(NodeView).collect())
+    }
+}
@@ -0,0 +1,71 @@
+extern crate colfer_rs;
+
+// Generated by `colf gen -o tests/gen tests/limits.colf`.
+#[path = "gen/limits.rs"]
+#[allow(dead_code)]
+mod limits;
+
+use colfer_rs::{ColferError, ColferResult, ColferSerializable};
+use limits::Profile;
+
+fn full<'a>() -> Profile<'a> {
+    Profile {
+        name: "12345678",
+        avatar: vec![1, 2, 3, 4],
+        roles: vec!["a", "bc", "defg"],
+        scores: vec![-1, 1],
+        ..Profile::default()
+    }
+}
+
+fn decode(data: &[u8]) -> ColferResult<usize> {
+    Profile::default().colf_unmarshal_binary(data)
+}
+
+#[test]
+fn at_limits() {
+    let want = full();
+    let data = want.colf_marshal_binary().unwrap();
+    let mut got = Profile::default();
+    assert_eq!(got.colf_unmarshal_binary(&data).unwrap(), data.len());
+    assert_eq!(got, want);
+}
+
+#[test]
+fn text_over_limit() {
+    let o = Profile { name: "123456789", ..full() };
+    let r = o.colf_marshal_binary();
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::name", overflow: 1 })), "{:?}", r);
+    let r = decode(b"\x00\x09123456789\x7F");
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::name", overflow: 1 })), "{:?}", r);
+
+    let o = Profile { avatar: vec![0; 6], ..full() };
+    let r = o.colf_marshal_binary();
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::avatar", overflow: 2 })), "{:?}", r);
+    let r = decode(b"\x01\x06\x00\x00\x00\x00\x00\x00\x7F");
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::avatar", overflow: 2 })), "{:?}", r);
+}
+
+#[test]
+fn list_over_limit() {
+    let o = Profile { roles: vec!["a", "b", "c", "d"], ..full() };
+    let r = o.colf_marshal_binary();
+    assert!(matches!(r, Err(ColferError::MaxListBreach { field: "self::roles", overflow: 1 })), "{:?}", r);
+    let r = decode(b"\x02\x04\x01a\x01b\x01c\x01d\x7F");
+    assert!(matches!(r, Err(ColferError::MaxListBreach { field: "self::roles", overflow: 1 })), "{:?}", r);
+
+    let o = Profile { scores: vec![1, 2, 3], ..full() };
+    let r = o.colf_marshal_binary();
+    assert!(matches!(r, Err(ColferError::MaxListBreach { field: "self::scores", overflow: 1 })), "{:?}", r);
+    let r = decode(b"\x03\x03\x02\x04\x06\x7F");
+    assert!(matches!(r, Err(ColferError::MaxListBreach { field: "self::scores", overflow: 1 })), "{:?}", r);
+}
+
+#[test]
+fn list_element_over_limit() {
+    let o = Profile { roles: vec!["a", "bcdef"], ..full() };
+    let r = o.colf_marshal_binary();
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::roles", overflow: 1 })), "{:?}", r);
+    let r = decode(b"\x02\x02\x01a\x05bcdef\x7F");
+    assert!(matches!(r, Err(ColferError::MaxSizeBreach { field: "self::roles", overflow: 1 })), "{:?}", r);
+}
@@ -0,0 +1,41 @@
+extern crate colfer_rs;
+
+// Generated by `colf gen -o tests/gen -t 'mapping.record.id=[u8; 16]' tests/mapping.colf`.
+#[path = "gen/mapping.rs"]
+#[allow(dead_code)]
+mod mapping;
+
+use colfer_rs::{ColferError, ColferSerializable};
+use mapping::{Record, RecordView};
+
+#[test]
+fn fixed_size_round_trip() {
+    let mut id = [0; 16];
+    for (i, b) in id.iter_mut().enumerate() {
+        *b = i as u8 + 1;
+    }
+    let want = Record {
+        id,
+        name: "x",
+        ..Record::default()
+    };
+    let data = want.colf_marshal_binary().unwrap();
+    assert_eq!(&data[..2], &[0, 16]);
+    assert_eq!(&data[2..18], &id);
+
+    let mut got = Record::default();
+    assert_eq!(got.colf_unmarshal_binary(&data).unwrap(), data.len());
+    assert_eq!(got, want);
+    assert_eq!(RecordView::new(&data).unwrap().id().unwrap(), &id);
+}
+
+#[test]
+fn wrong_length_rejected() {
+    for &n in [1, 15, 17].iter() {
+        let mut data = vec![0x01, 0x01, b'x', 0x00, n as u8];
+        data.extend((0..n).map(|i| i as u8));
+        data.push(0x7F);
+        let r = Record::default().colf_unmarshal_binary(&data);
+        assert!(matches!(r, Err(ColferError::InvalidValue { byte: 3 })), "{} bytes: {:?}", n, r);
+    }
+}
@@ -0,0 +1,73 @@
+extern crate colfer_rs;
+
+// Generated by `colf gen -o tests/gen tests/forest.colf tests/grove.colf`.
+#[path = "gen/forest.rs"]
+#[allow(dead_code)]
+mod forest;
+#[path = "gen/grove.rs"]
+#[allow(dead_code)]
+mod grove;
+
+use colfer_rs::ColferSerializable;
+use forest::{Tree, TreeView};
+use grove::Leaf;
+
+fn forest<'a>() -> Tree<'a> {
+    Tree {
+        name: "oak",
+        leaves: vec![
+            Some(Leaf {
+                label: "first",
+                trees: vec![Some(Tree {
+                    name: "sapling",
+                    ..Tree::default()
+                })],
+                ..Leaf::default()
+            }),
+            Some(Leaf::default()),
+        ],
+        top: Some(Box::new(Leaf {
+            label: "top",
+            tree: Some(Box::new(Tree {
+                name: "inner",
+                top: Some(Box::new(Leaf {
+                    label: "deepest",
+                    ..Leaf::default()
+                })),
+                ..Tree::default()
+            })),
+            ..Leaf::default()
+        })),
+        unknown_fields: &[],
+    }
+}
+
+#[test]
+fn mutual_recursion_round_trip() {
+    let want = forest();
+    let data = want.colf_marshal_binary().unwrap();
+    assert_eq!(data.len(), want.colf_marshal_len().unwrap());
+
+    let mut got = Tree::default();
+    assert_eq!(got.colf_unmarshal_binary(&data).unwrap(), data.len());
+    assert_eq!(got, want);
+
+    let mut again = Tree::default();
+    assert_eq!(again.colf_unmarshal_into(&data).unwrap(), data.len());
+    assert_eq!(again, want);
+}
+
+#[test]
+fn mutual_recursion_view() {
+    let data = forest().colf_marshal_binary().unwrap();
+    let v = TreeView::new(&data).unwrap();
+    assert_eq!(v.name().unwrap(), "oak");
+    let leaves = v.leaves().unwrap();
+    assert_eq!(leaves.len(), 2);
+    assert_eq!(leaves[0].label().unwrap(), "first");
+    assert_eq!(leaves[0].trees().unwrap()[0].name().unwrap(), "sapling");
+    let top = v.top().unwrap().unwrap();
+    let inner = top.tree().unwrap().unwrap();
+    assert_eq!(inner.name().unwrap(), "inner");
+    assert_eq!(inner.top().unwrap().unwrap().label().unwrap(), "deepest");
+}
@@ -0,0 +1,90 @@
+extern crate colfer_rs;
+
+// Generated by `colf gen -o tests/gen tests/reuse.colf`.
+#[path = "gen/reuse.rs"]
+#[allow(dead_code)]
+mod reuse;
+
+use colfer_rs::ColferSerializable;
+use reuse::Node;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the heap allocations of the current thread, as the test harness allocates on
+// threads of its own.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocs<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCS.with(Cell::get);
+    f();
+    ALLOCS.with(Cell::get) - before
+}
+
+fn leaf<'a>(name: &'a str, data: &[u8]) -> Option<Node<'a>> {
+    Some(Node {
+        name,
+        data: data.to_vec(),
+        tags: vec!["x", "y"],
+        values: vec![-1, 2],
+        ..Node::default()
+    })
+}
+
+fn tree<'a>() -> Node<'a> {
+    Node {
+        name: "root",
+        data: vec![1, 2, 3],
+        tags: vec!["a", "b", "c"],
+        values: vec![7, -8, 9],
+        child: Some(Box::new(Node {
+            children: vec![leaf("grandchild", &[4, 5])],
+            ..leaf("child", &[6]).unwrap()
+        })),
+        children: vec![leaf("first", &[7, 8]), leaf("second", &[9])],
+        unknown_fields: &[],
+    }
+}
+
+#[test]
+fn unmarshal_into_reuses_allocations() {
+    let want = tree();
+    let data = want.colf_marshal_binary().unwrap();
+    let mut got = Node::default();
+    assert!(allocs(|| assert_eq!(got.colf_unmarshal_into(&data).unwrap(), data.len())) > 0);
+    assert_eq!(got, want);
+
+    assert_eq!(allocs(|| assert_eq!(got.colf_unmarshal_into(&data).unwrap(), data.len())), 0);
+    assert_eq!(got, want);
+
+    // Less data truncates lists and drops what it has no value for, still without allocating.
+    let less = Node {
+        children: vec![leaf("only", &[1])],
+        ..Node::default()
+    };
+    let data = less.colf_marshal_binary().unwrap();
+    assert_eq!(allocs(|| assert_eq!(got.colf_unmarshal_into(&data).unwrap(), data.len())), 0);
+    assert_eq!(got, less);
+}